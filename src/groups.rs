@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+
+use rust_extensions::lazy::LazyVec;
+
+pub struct Groups {
+    pub group_connections: HashMap<String, HashMap<String, ()>>,
+    pub connection_groups: HashMap<String, HashMap<String, ()>>,
+}
+
+impl Default for Groups {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Groups {
+    pub fn new() -> Self {
+        Self {
+            group_connections: HashMap::new(),
+            connection_groups: HashMap::new(),
+        }
+    }
+
+    pub fn add_to_group(&mut self, connection_id: &str, group: &str) {
+        self.connection_groups
+            .entry(connection_id.to_string())
+            .or_default()
+            .insert(group.to_string(), ());
+
+        self.group_connections
+            .entry(group.to_string())
+            .or_default()
+            .insert(connection_id.to_string(), ());
+    }
+
+    pub fn remove_from_group(&mut self, connection_id: &str, group: &str) {
+        if let Some(groups) = self.connection_groups.get_mut(connection_id) {
+            groups.remove(group);
+
+            if groups.is_empty() {
+                self.connection_groups.remove(connection_id);
+            }
+        }
+
+        self.remove_group_connection(group, connection_id);
+    }
+
+    pub fn remove_connection(&mut self, connection_id: &str) {
+        if let Some(groups) = self.connection_groups.remove(connection_id) {
+            for group in groups.keys() {
+                self.remove_group_connection(group, connection_id);
+            }
+        }
+    }
+
+    fn remove_group_connection(&mut self, group: &str, connection_id: &str) {
+        if let Some(connections) = self.group_connections.get_mut(group) {
+            connections.remove(connection_id);
+
+            if connections.is_empty() {
+                self.group_connections.remove(group);
+            }
+        }
+    }
+
+    pub fn get_connection_groups(&self, connection_id: &str) -> Option<Vec<String>> {
+        let groups = self.connection_groups.get(connection_id)?;
+        Some(groups.keys().cloned().collect())
+    }
+
+    pub fn get_group_connections(&self, group: &str) -> Option<Vec<String>> {
+        let connections = self.group_connections.get(group)?;
+        Some(connections.keys().cloned().collect())
+    }
+
+    pub fn get_groups_connections(&self, groups: &[&str]) -> Option<Vec<String>> {
+        let mut added = HashMap::new();
+        let mut result = LazyVec::new();
+
+        for group in groups {
+            if let Some(connections) = self.group_connections.get(*group) {
+                for connection_id in connections.keys() {
+                    if added.insert(connection_id.as_str(), ()).is_none() {
+                        result.add(connection_id.to_string());
+                    }
+                }
+            }
+        }
+
+        result.get_result()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Groups;
+
+    #[test]
+    fn test_connection_in_several_groups() {
+        let mut groups = Groups::new();
+
+        groups.add_to_group("c1", "BTCUSD");
+        groups.add_to_group("c1", "ETHUSD");
+        groups.add_to_group("c2", "BTCUSD");
+
+        assert_eq!(groups.get_group_connections("BTCUSD").unwrap().len(), 2);
+        assert_eq!(groups.get_group_connections("ETHUSD").unwrap().len(), 1);
+        assert_eq!(groups.get_connection_groups("c1").unwrap().len(), 2);
+
+        let connections = groups
+            .get_groups_connections(&["BTCUSD", "ETHUSD"])
+            .unwrap();
+
+        assert_eq!(connections.len(), 2);
+    }
+
+    #[test]
+    fn test_remove_from_group() {
+        let mut groups = Groups::new();
+
+        groups.add_to_group("c1", "BTCUSD");
+        groups.add_to_group("c1", "ETHUSD");
+
+        groups.remove_from_group("c1", "BTCUSD");
+
+        assert!(groups.get_group_connections("BTCUSD").is_none());
+        assert_eq!(groups.get_group_connections("ETHUSD").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_remove_connection() {
+        let mut groups = Groups::new();
+
+        groups.add_to_group("c1", "BTCUSD");
+        groups.add_to_group("c1", "ETHUSD");
+        groups.add_to_group("c2", "ETHUSD");
+
+        groups.remove_connection("c1");
+
+        assert!(groups.get_group_connections("BTCUSD").is_none());
+        assert!(groups.get_connection_groups("c1").is_none());
+        assert_eq!(
+            groups.get_group_connections("ETHUSD").unwrap(),
+            vec!["c2".to_string()]
+        );
+    }
+}
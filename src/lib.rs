@@ -1,28 +1,61 @@
+mod groups;
 pub mod messages;
 mod middleware;
 mod middleware_builder;
+mod middleware_settings;
 mod my_signal_r_action_callback;
 mod my_signal_r_actions;
+mod my_signal_r_authenticator;
 mod my_signal_r_callbacks;
+mod my_signal_r_stream_callback;
+mod my_signal_r_upload_callback;
+mod outbound_queue;
+mod outbound_writer;
 mod process_connect;
 mod process_disconnect;
+mod process_stream_invocation;
+mod signal_r_conflating_publisher;
 mod signal_r_connection;
+mod signal_r_connection_settings;
 mod signal_r_connections_list;
+mod signal_r_identity;
+mod signal_r_invocation_frame;
+mod signal_r_invoke_error;
 mod signal_r_message_publisher;
 mod signal_r_publishers_builder;
+mod signal_r_transfer_format;
+mod signal_r_transport;
 mod signalr_liveness_loop;
+mod stateful_reconnect;
+mod tag_query;
 mod tags;
 mod web_socket_callbacks;
+pub use groups::*;
 pub use middleware::*;
 pub use middleware_builder::*;
+pub use middleware_settings::*;
 pub use my_signal_r_action_callback::*;
+pub use my_signal_r_authenticator::*;
 pub use my_signal_r_callbacks::*;
+pub use my_signal_r_stream_callback::*;
+pub use my_signal_r_upload_callback::*;
+pub use outbound_queue::*;
 use process_connect::process_connect;
 use process_disconnect::process_disconnect;
+use process_stream_invocation::process_stream_invocation;
+pub use signal_r_conflating_publisher::*;
 pub use signal_r_connection::*;
+pub use signal_r_connection_settings::*;
 pub use signal_r_connections_list::*;
+pub use signal_r_identity::*;
+pub use signal_r_invocation_frame::*;
+pub use signal_r_invoke_error::*;
 pub use signal_r_message_publisher::*;
 pub use signal_r_publishers_builder::*;
+pub use signal_r_transfer_format::*;
+pub use signal_r_transport::*;
+pub use stateful_reconnect::*;
+pub use tag_query::*;
 pub use tags::Tags;
 pub use tags::*;
 pub use web_socket_callbacks::WebSocketCallbacks;
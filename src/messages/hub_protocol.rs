@@ -0,0 +1,33 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HubProtocol {
+    Json,
+    MessagePack,
+}
+
+impl HubProtocol {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "json" => Some(Self::Json),
+            "messagepack" => Some(Self::MessagePack),
+            _ => None,
+        }
+    }
+
+    pub fn get_max_version(&self) -> usize {
+        match self {
+            Self::Json => 2,
+            Self::MessagePack => 2,
+        }
+    }
+
+    pub fn is_version_supported(&self, version: usize) -> bool {
+        version >= 1 && version <= self.get_max_version()
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::MessagePack => "messagepack",
+        }
+    }
+}
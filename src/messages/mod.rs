@@ -1,4 +1,21 @@
+mod hub_protocol;
+pub mod msgpack;
+mod msgpack_payloads_generations;
 mod payloads_generations;
+mod signalr_cancel_invocation_message;
+mod signalr_completion_message;
+mod signalr_handshake_request;
 mod signalr_message;
+mod signalr_sequence_message;
+mod signalr_stream_item_message;
+mod text_framing;
+pub use hub_protocol::*;
+pub use msgpack_payloads_generations::*;
 pub use payloads_generations::*;
+pub use signalr_cancel_invocation_message::*;
+pub use signalr_completion_message::*;
+pub use signalr_handshake_request::*;
 pub use signalr_message::*;
+pub use signalr_sequence_message::*;
+pub use signalr_stream_item_message::*;
+pub use text_framing::*;
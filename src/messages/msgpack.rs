@@ -0,0 +1,1022 @@
+pub fn write_varint_length(mut len: usize, out: &mut Vec<u8>) {
+    loop {
+        let mut byte = (len & 0x7f) as u8;
+        len >>= 7;
+        if len > 0 {
+            byte |= 0x80;
+        }
+        out.push(byte);
+
+        if len == 0 {
+            break;
+        }
+    }
+}
+
+pub fn split_binary_frames(data: &[u8]) -> Result<Vec<&[u8]>, String> {
+    let mut result = Vec::new();
+    let mut pos = 0;
+
+    while pos < data.len() {
+        let mut len: usize = 0;
+        let mut shift = 0;
+
+        loop {
+            if shift > 28 {
+                return Err("Binary message length prefix is too long".to_string());
+            }
+
+            let byte = match data.get(pos) {
+                Some(byte) => *byte,
+                None => return Err("Binary message length prefix is incomplete".to_string()),
+            };
+            pos += 1;
+
+            len |= ((byte & 0x7f) as usize) << shift;
+            shift += 7;
+
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+
+        if pos + len > data.len() {
+            return Err(format!(
+                "Binary message is incomplete. Expected {} bytes, but {} left",
+                len,
+                data.len() - pos
+            ));
+        }
+
+        result.push(&data[pos..pos + len]);
+        pos += len;
+    }
+
+    Ok(result)
+}
+
+pub fn write_nil(out: &mut Vec<u8>) {
+    out.push(0xc0);
+}
+
+pub fn write_bool(value: bool, out: &mut Vec<u8>) {
+    out.push(if value { 0xc3 } else { 0xc2 });
+}
+
+pub fn write_int(value: i64, out: &mut Vec<u8>) {
+    if value >= 0 {
+        write_uint(value as u64, out);
+    } else if value >= -32 {
+        out.push(value as i8 as u8);
+    } else if value >= i8::MIN as i64 {
+        out.push(0xd0);
+        out.push(value as i8 as u8);
+    } else if value >= i16::MIN as i64 {
+        out.push(0xd1);
+        out.extend_from_slice(&(value as i16).to_be_bytes());
+    } else if value >= i32::MIN as i64 {
+        out.push(0xd2);
+        out.extend_from_slice(&(value as i32).to_be_bytes());
+    } else {
+        out.push(0xd3);
+        out.extend_from_slice(&value.to_be_bytes());
+    }
+}
+
+pub fn write_uint(value: u64, out: &mut Vec<u8>) {
+    if value <= 0x7f {
+        out.push(value as u8);
+    } else if value <= u8::MAX as u64 {
+        out.push(0xcc);
+        out.push(value as u8);
+    } else if value <= u16::MAX as u64 {
+        out.push(0xcd);
+        out.extend_from_slice(&(value as u16).to_be_bytes());
+    } else if value <= u32::MAX as u64 {
+        out.push(0xce);
+        out.extend_from_slice(&(value as u32).to_be_bytes());
+    } else {
+        out.push(0xcf);
+        out.extend_from_slice(&value.to_be_bytes());
+    }
+}
+
+pub fn write_f64(value: f64, out: &mut Vec<u8>) {
+    out.push(0xcb);
+    out.extend_from_slice(&value.to_be_bytes());
+}
+
+pub fn write_str(value: &str, out: &mut Vec<u8>) {
+    let len = value.len();
+    if len <= 31 {
+        out.push(0xa0 | len as u8);
+    } else if len <= u8::MAX as usize {
+        out.push(0xd9);
+        out.push(len as u8);
+    } else if len <= u16::MAX as usize {
+        out.push(0xda);
+        out.extend_from_slice(&(len as u16).to_be_bytes());
+    } else {
+        out.push(0xdb);
+        out.extend_from_slice(&(len as u32).to_be_bytes());
+    }
+
+    out.extend_from_slice(value.as_bytes());
+}
+
+pub fn write_array_len(len: usize, out: &mut Vec<u8>) {
+    if len <= 15 {
+        out.push(0x90 | len as u8);
+    } else if len <= u16::MAX as usize {
+        out.push(0xdc);
+        out.extend_from_slice(&(len as u16).to_be_bytes());
+    } else {
+        out.push(0xdd);
+        out.extend_from_slice(&(len as u32).to_be_bytes());
+    }
+}
+
+pub fn write_map_len(len: usize, out: &mut Vec<u8>) {
+    if len <= 15 {
+        out.push(0x80 | len as u8);
+    } else if len <= u16::MAX as usize {
+        out.push(0xde);
+        out.extend_from_slice(&(len as u16).to_be_bytes());
+    } else {
+        out.push(0xdf);
+        out.extend_from_slice(&(len as u32).to_be_bytes());
+    }
+}
+
+// Contracts hand us serialized JSON (SignalrContractSerializer) and hub handlers take raw
+// JSON argument slices, so the codec converts token by token between the two encodings
+// instead of building an intermediate value tree through rmpv, and the crate keeps no
+// extra MessagePack dependency.
+pub fn json_to_msgpack(json: &[u8], out: &mut Vec<u8>) -> Result<(), String> {
+    let mut reader = JsonToMsgPack {
+        data: json,
+        pos: 0,
+        depth: 0,
+    };
+    reader.convert_value(out)?;
+    reader.skip_white_spaces();
+
+    if reader.pos != json.len() {
+        return Err(format!(
+            "Unexpected symbol after json value at position {}",
+            reader.pos
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn msgpack_to_json(data: &[u8], out: &mut Vec<u8>) -> Result<(), String> {
+    let mut reader = MsgPackReader::new(data);
+    reader.read_as_json(out)?;
+
+    if reader.pos != data.len() {
+        return Err(format!(
+            "Unexpected byte after msgpack value at position {}",
+            reader.pos
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn msgpack_message_to_json(data: &[u8]) -> Result<String, String> {
+    let mut reader = MsgPackReader::new(data);
+    let items = reader.read_array_len()?;
+    let message_type = reader.read_int()?;
+
+    let mut out = Vec::new();
+    out.extend_from_slice("{\"type\":".as_bytes());
+    out.extend_from_slice(message_type.to_string().as_bytes());
+
+    match message_type {
+        1 | 4 => {
+            reader.skip()?;
+            if let Some(invocation_id) = reader.read_optional_str()? {
+                write_json_field_name("invocationId", &mut out);
+                write_json_string(invocation_id, &mut out);
+            }
+            write_json_field_name("target", &mut out);
+            write_json_string(reader.read_str()?, &mut out);
+            write_json_field_name("arguments", &mut out);
+            reader.read_as_json(&mut out)?;
+
+            if items > 5 {
+                write_json_field_name("streamIds", &mut out);
+                reader.read_as_json(&mut out)?;
+            }
+        }
+        2 => {
+            reader.skip()?;
+            write_json_field_name("invocationId", &mut out);
+            write_json_string(reader.read_str()?, &mut out);
+            write_json_field_name("item", &mut out);
+            reader.read_as_json(&mut out)?;
+        }
+        3 => {
+            reader.skip()?;
+            write_json_field_name("invocationId", &mut out);
+            write_json_string(reader.read_str()?, &mut out);
+
+            match reader.read_int()? {
+                1 => {
+                    write_json_field_name("error", &mut out);
+                    write_json_string(reader.read_str()?, &mut out);
+                }
+                3 => {
+                    write_json_field_name("result", &mut out);
+                    reader.read_as_json(&mut out)?;
+                }
+                _ => {}
+            }
+        }
+        5 => {
+            reader.skip()?;
+            write_json_field_name("invocationId", &mut out);
+            write_json_string(reader.read_str()?, &mut out);
+        }
+        7 => {
+            if let Some(error) = reader.read_optional_str()? {
+                write_json_field_name("error", &mut out);
+                write_json_string(error, &mut out);
+            }
+
+            if items > 2 {
+                write_json_field_name("allowReconnect", &mut out);
+                reader.read_as_json(&mut out)?;
+            }
+        }
+        8 | 9 => {
+            write_json_field_name("sequenceId", &mut out);
+            reader.read_as_json(&mut out)?;
+        }
+        _ => {}
+    }
+
+    out.push(b'}');
+
+    Ok(String::from_utf8(out).unwrap())
+}
+
+fn write_json_field_name(name: &str, out: &mut Vec<u8>) {
+    out.push(b',');
+    write_json_string(name, out);
+    out.push(b':');
+}
+
+fn write_json_string(value: &str, out: &mut Vec<u8>) {
+    out.push(b'"');
+    for c in value.chars() {
+        match c {
+            '"' => out.extend_from_slice("\\\"".as_bytes()),
+            '\\' => out.extend_from_slice("\\\\".as_bytes()),
+            '\n' => out.extend_from_slice("\\n".as_bytes()),
+            '\r' => out.extend_from_slice("\\r".as_bytes()),
+            '\t' => out.extend_from_slice("\\t".as_bytes()),
+            c if (c as u32) < 0x20 => {
+                out.extend_from_slice(format!("\\u{:04x}", c as u32).as_bytes());
+            }
+            c => {
+                let mut buffer = [0u8; 4];
+                out.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+            }
+        }
+    }
+    out.push(b'"');
+}
+
+fn write_base64(value: &[u8], out: &mut Vec<u8>) {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    for chunk in value.chunks(3) {
+        let b0 = chunk[0] as u32;
+        let b1 = chunk.get(1).copied().unwrap_or(0) as u32;
+        let b2 = chunk.get(2).copied().unwrap_or(0) as u32;
+        let triple = (b0 << 16) | (b1 << 8) | b2;
+
+        out.push(ALPHABET[(triple >> 18) as usize & 0x3f]);
+        out.push(ALPHABET[(triple >> 12) as usize & 0x3f]);
+
+        if chunk.len() > 1 {
+            out.push(ALPHABET[(triple >> 6) as usize & 0x3f]);
+        } else {
+            out.push(b'=');
+        }
+
+        if chunk.len() > 2 {
+            out.push(ALPHABET[triple as usize & 0x3f]);
+        } else {
+            out.push(b'=');
+        }
+    }
+}
+
+const MAX_NESTING_DEPTH: usize = 64;
+
+pub struct MsgPackReader<'s> {
+    data: &'s [u8],
+    pos: usize,
+    depth: usize,
+}
+
+impl<'s> MsgPackReader<'s> {
+    pub fn new(data: &'s [u8]) -> Self {
+        Self {
+            data,
+            pos: 0,
+            depth: 0,
+        }
+    }
+
+    fn enter_nested(&mut self) -> Result<(), String> {
+        if self.depth >= MAX_NESTING_DEPTH {
+            return Err(format!(
+                "Msgpack nesting depth exceeds {} at position {}",
+                MAX_NESTING_DEPTH, self.pos
+            ));
+        }
+
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Result<&'s [u8], String> {
+        if self.pos + len > self.data.len() {
+            return Err(format!(
+                "Unexpected end of msgpack payload at position {}",
+                self.pos
+            ));
+        }
+
+        let result = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(result)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, String> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    fn read_be(&mut self, len: usize) -> Result<u64, String> {
+        let mut result = 0u64;
+        for byte in self.read_bytes(len)? {
+            result = (result << 8) | *byte as u64;
+        }
+        Ok(result)
+    }
+
+    fn peek(&self) -> Result<u8, String> {
+        match self.data.get(self.pos) {
+            Some(byte) => Ok(*byte),
+            None => Err(format!(
+                "Unexpected end of msgpack payload at position {}",
+                self.pos
+            )),
+        }
+    }
+
+    pub fn read_array_len(&mut self) -> Result<usize, String> {
+        let marker = self.read_u8()?;
+        match marker {
+            0x90..=0x9f => Ok((marker & 0x0f) as usize),
+            0xdc => Ok(self.read_be(2)? as usize),
+            0xdd => Ok(self.read_be(4)? as usize),
+            _ => Err(format!("Msgpack array is expected. Marker: {:#x}", marker)),
+        }
+    }
+
+    pub fn read_int(&mut self) -> Result<i64, String> {
+        let marker = self.read_u8()?;
+        match marker {
+            0x00..=0x7f => Ok(marker as i64),
+            0xe0..=0xff => Ok(marker as i8 as i64),
+            0xcc => Ok(self.read_be(1)? as i64),
+            0xcd => Ok(self.read_be(2)? as i64),
+            0xce => Ok(self.read_be(4)? as i64),
+            0xcf => Ok(self.read_be(8)? as i64),
+            0xd0 => Ok(self.read_be(1)? as u8 as i8 as i64),
+            0xd1 => Ok(self.read_be(2)? as u16 as i16 as i64),
+            0xd2 => Ok(self.read_be(4)? as u32 as i32 as i64),
+            0xd3 => Ok(self.read_be(8)? as i64),
+            _ => Err(format!(
+                "Msgpack integer is expected. Marker: {:#x}",
+                marker
+            )),
+        }
+    }
+
+    pub fn read_str(&mut self) -> Result<&'s str, String> {
+        let marker = self.read_u8()?;
+        let len = match marker {
+            0xa0..=0xbf => (marker & 0x1f) as usize,
+            0xd9 => self.read_be(1)? as usize,
+            0xda => self.read_be(2)? as usize,
+            0xdb => self.read_be(4)? as usize,
+            _ => return Err(format!("Msgpack string is expected. Marker: {:#x}", marker)),
+        };
+
+        let bytes = self.read_bytes(len)?;
+        std::str::from_utf8(bytes).map_err(|err| format!("Invalid utf8 string: {}", err))
+    }
+
+    pub fn read_optional_str(&mut self) -> Result<Option<&'s str>, String> {
+        if self.peek()? == 0xc0 {
+            self.pos += 1;
+            return Ok(None);
+        }
+
+        Ok(Some(self.read_str()?))
+    }
+
+    pub fn skip(&mut self) -> Result<(), String> {
+        let mut sink = Vec::new();
+        self.read_as_json(&mut sink)
+    }
+
+    pub fn read_as_json(&mut self, out: &mut Vec<u8>) -> Result<(), String> {
+        let marker = self.peek()?;
+        match marker {
+            0x00..=0x7f | 0xe0..=0xff | 0xcc..=0xce | 0xd0..=0xd3 => {
+                out.extend_from_slice(self.read_int()?.to_string().as_bytes());
+            }
+            0xcf => {
+                self.pos += 1;
+                out.extend_from_slice(self.read_be(8)?.to_string().as_bytes());
+            }
+            0xc0 => {
+                self.pos += 1;
+                out.extend_from_slice("null".as_bytes());
+            }
+            0xc2 => {
+                self.pos += 1;
+                out.extend_from_slice("false".as_bytes());
+            }
+            0xc3 => {
+                self.pos += 1;
+                out.extend_from_slice("true".as_bytes());
+            }
+            0xca => {
+                self.pos += 1;
+                let value = f32::from_bits(self.read_be(4)? as u32) as f64;
+                write_json_float(value, out);
+            }
+            0xcb => {
+                self.pos += 1;
+                let value = f64::from_bits(self.read_be(8)?);
+                write_json_float(value, out);
+            }
+            0xa0..=0xbf | 0xd9..=0xdb => {
+                let value = self.read_str()?;
+                write_json_string(value, out);
+            }
+            0xc4..=0xc6 => {
+                self.pos += 1;
+                let len = self.read_be(1 << (marker - 0xc4))? as usize;
+                let value = self.read_bytes(len)?;
+                out.push(b'"');
+                write_base64(value, out);
+                out.push(b'"');
+            }
+            0x90..=0x9f | 0xdc | 0xdd => {
+                self.enter_nested()?;
+                let len = self.read_array_len()?;
+                out.push(b'[');
+                for index in 0..len {
+                    if index > 0 {
+                        out.push(b',');
+                    }
+                    self.read_as_json(out)?;
+                }
+                out.push(b']');
+                self.depth -= 1;
+            }
+            0x80..=0x8f | 0xde | 0xdf => {
+                self.enter_nested()?;
+                self.pos += 1;
+                let len = match marker {
+                    0xde => self.read_be(2)? as usize,
+                    0xdf => self.read_be(4)? as usize,
+                    _ => (marker & 0x0f) as usize,
+                };
+
+                out.push(b'{');
+                for index in 0..len {
+                    if index > 0 {
+                        out.push(b',');
+                    }
+
+                    let mut key = Vec::new();
+                    self.read_as_json(&mut key)?;
+                    if key.first() == Some(&b'"') {
+                        out.extend_from_slice(key.as_slice());
+                    } else {
+                        write_json_string(&String::from_utf8_lossy(&key), out);
+                    }
+
+                    out.push(b':');
+                    self.read_as_json(out)?;
+                }
+                out.push(b'}');
+                self.depth -= 1;
+            }
+            0xd4..=0xd8 | 0xc7..=0xc9 => {
+                self.pos += 1;
+                let len = match marker {
+                    0xd4..=0xd8 => 1 << (marker - 0xd4),
+                    _ => self.read_be(1 << (marker - 0xc7))? as usize,
+                };
+                let ext_type = self.read_u8()? as i8;
+                let value = self.read_bytes(len)?;
+
+                if ext_type != TIMESTAMP_EXT_TYPE {
+                    return Err(format!("Unsupported msgpack ext type: {}", ext_type));
+                }
+
+                write_json_timestamp(value, out)?;
+            }
+            _ => {
+                return Err(format!("Unsupported msgpack marker: {:#x}", marker));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+const TIMESTAMP_EXT_TYPE: i8 = -1;
+
+fn write_json_timestamp(value: &[u8], out: &mut Vec<u8>) -> Result<(), String> {
+    let (seconds, nanoseconds) = match value.len() {
+        4 => (u32::from_be_bytes(value.try_into().unwrap()) as i64, 0),
+        8 => {
+            let value = u64::from_be_bytes(value.try_into().unwrap());
+            ((value & 0x3_ffff_ffff) as i64, (value >> 34) as u32)
+        }
+        12 => (
+            i64::from_be_bytes(value[4..].try_into().unwrap()),
+            u32::from_be_bytes(value[..4].try_into().unwrap()),
+        ),
+        _ => return Err(format!("Invalid msgpack timestamp length: {}", value.len())),
+    };
+
+    if nanoseconds >= 1_000_000_000 {
+        return Err(format!(
+            "Invalid msgpack timestamp nanoseconds: {}",
+            nanoseconds
+        ));
+    }
+
+    let days = seconds.div_euclid(86400);
+    let seconds_of_day = seconds.rem_euclid(86400);
+
+    // Converting days since 1970-01-01 into a civil date (proleptic Gregorian calendar)
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    let mut result = format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day % 3600 / 60,
+        seconds_of_day % 60
+    );
+
+    if nanoseconds > 0 {
+        let fraction = format!("{:09}", nanoseconds);
+        result.push('.');
+        result.push_str(fraction.trim_end_matches('0'));
+    }
+
+    result.push('Z');
+
+    write_json_string(result.as_str(), out);
+    Ok(())
+}
+
+fn write_json_float(value: f64, out: &mut Vec<u8>) {
+    if value.is_finite() {
+        out.extend_from_slice(value.to_string().as_bytes());
+    } else {
+        out.extend_from_slice("null".as_bytes());
+    }
+}
+
+struct JsonToMsgPack<'s> {
+    data: &'s [u8],
+    pos: usize,
+    depth: usize,
+}
+
+impl<'s> JsonToMsgPack<'s> {
+    fn skip_white_spaces(&mut self) {
+        while let Some(byte) = self.data.get(self.pos) {
+            if !byte.is_ascii_whitespace() {
+                break;
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Result<u8, String> {
+        match self.data.get(self.pos) {
+            Some(byte) => Ok(*byte),
+            None => Err(format!("Unexpected end of json at position {}", self.pos)),
+        }
+    }
+
+    fn expect_literal(&mut self, literal: &str) -> Result<(), String> {
+        if self.data[self.pos..].starts_with(literal.as_bytes()) {
+            self.pos += literal.len();
+            Ok(())
+        } else {
+            Err(format!("Invalid json literal at position {}", self.pos))
+        }
+    }
+
+    fn enter_nested(&mut self) -> Result<(), String> {
+        if self.depth >= MAX_NESTING_DEPTH {
+            return Err(format!(
+                "Json nesting depth exceeds {} at position {}",
+                MAX_NESTING_DEPTH, self.pos
+            ));
+        }
+
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn convert_value(&mut self, out: &mut Vec<u8>) -> Result<(), String> {
+        self.skip_white_spaces();
+
+        match self.peek()? {
+            b'{' => {
+                self.enter_nested()?;
+                self.pos += 1;
+                let mut items = 0;
+                let mut buffer = Vec::new();
+
+                self.skip_white_spaces();
+                if self.peek()? == b'}' {
+                    self.pos += 1;
+                } else {
+                    loop {
+                        self.skip_white_spaces();
+                        let key = self.read_string()?;
+                        write_str(key.as_str(), &mut buffer);
+
+                        self.skip_white_spaces();
+                        if self.peek()? != b':' {
+                            return Err(format!("':' is expected at position {}", self.pos));
+                        }
+                        self.pos += 1;
+
+                        self.convert_value(&mut buffer)?;
+                        items += 1;
+
+                        self.skip_white_spaces();
+                        match self.peek()? {
+                            b',' => self.pos += 1,
+                            b'}' => {
+                                self.pos += 1;
+                                break;
+                            }
+                            _ => {
+                                return Err(format!(
+                                    "',' or '}}' is expected at position {}",
+                                    self.pos
+                                ))
+                            }
+                        }
+                    }
+                }
+
+                write_map_len(items, out);
+                out.extend_from_slice(buffer.as_slice());
+                self.depth -= 1;
+            }
+            b'[' => {
+                self.enter_nested()?;
+                self.pos += 1;
+                let mut items = 0;
+                let mut buffer = Vec::new();
+
+                self.skip_white_spaces();
+                if self.peek()? == b']' {
+                    self.pos += 1;
+                } else {
+                    loop {
+                        self.convert_value(&mut buffer)?;
+                        items += 1;
+
+                        self.skip_white_spaces();
+                        match self.peek()? {
+                            b',' => self.pos += 1,
+                            b']' => {
+                                self.pos += 1;
+                                break;
+                            }
+                            _ => {
+                                return Err(format!(
+                                    "',' or ']' is expected at position {}",
+                                    self.pos
+                                ))
+                            }
+                        }
+                    }
+                }
+
+                write_array_len(items, out);
+                out.extend_from_slice(buffer.as_slice());
+                self.depth -= 1;
+            }
+            b'"' => {
+                let value = self.read_string()?;
+                write_str(value.as_str(), out);
+            }
+            b't' => {
+                self.expect_literal("true")?;
+                write_bool(true, out);
+            }
+            b'f' => {
+                self.expect_literal("false")?;
+                write_bool(false, out);
+            }
+            b'n' => {
+                self.expect_literal("null")?;
+                write_nil(out);
+            }
+            _ => self.convert_number(out)?,
+        }
+
+        Ok(())
+    }
+
+    fn convert_number(&mut self, out: &mut Vec<u8>) -> Result<(), String> {
+        let start = self.pos;
+        let mut is_float = false;
+
+        while let Some(byte) = self.data.get(self.pos) {
+            match byte {
+                b'0'..=b'9' | b'-' | b'+' => {}
+                b'.' | b'e' | b'E' => is_float = true,
+                _ => break,
+            }
+            self.pos += 1;
+        }
+
+        let value = std::str::from_utf8(&self.data[start..self.pos]).unwrap();
+
+        if value.is_empty() {
+            return Err(format!("Invalid json value at position {}", start));
+        }
+
+        if !is_float {
+            if let Ok(value) = value.parse::<i64>() {
+                write_int(value, out);
+                return Ok(());
+            }
+
+            if let Ok(value) = value.parse::<u64>() {
+                write_uint(value, out);
+                return Ok(());
+            }
+        }
+
+        match value.parse::<f64>() {
+            Ok(value) => {
+                write_f64(value, out);
+                Ok(())
+            }
+            Err(_) => Err(format!("Invalid json number '{}'", value)),
+        }
+    }
+
+    fn read_hex4(&mut self) -> Result<u32, String> {
+        if self.pos + 4 > self.data.len() {
+            return Err(format!("Invalid unicode escape at position {}", self.pos));
+        }
+
+        let hex = std::str::from_utf8(&self.data[self.pos..self.pos + 4])
+            .map_err(|_| format!("Invalid unicode escape at position {}", self.pos))?;
+        let result = u32::from_str_radix(hex, 16)
+            .map_err(|_| format!("Invalid unicode escape at position {}", self.pos))?;
+        self.pos += 4;
+        Ok(result)
+    }
+
+    fn read_string(&mut self) -> Result<String, String> {
+        if self.peek()? != b'"' {
+            return Err(format!("String is expected at position {}", self.pos));
+        }
+        self.pos += 1;
+
+        let mut result = Vec::new();
+
+        loop {
+            let byte = self.peek()?;
+            self.pos += 1;
+
+            match byte {
+                b'"' => break,
+                b'\\' => {
+                    let escaped = self.peek()?;
+                    self.pos += 1;
+                    match escaped {
+                        b'"' => result.push(b'"'),
+                        b'\\' => result.push(b'\\'),
+                        b'/' => result.push(b'/'),
+                        b'b' => result.push(0x08),
+                        b'f' => result.push(0x0c),
+                        b'n' => result.push(b'\n'),
+                        b'r' => result.push(b'\r'),
+                        b't' => result.push(b'\t'),
+                        b'u' => {
+                            let mut code = self.read_hex4()?;
+
+                            if (0xd800..0xdc00).contains(&code)
+                                && self.data[self.pos..].starts_with(b"\\u")
+                            {
+                                self.pos += 2;
+                                let low = self.read_hex4()?;
+
+                                if (0xdc00..=0xdfff).contains(&low) {
+                                    code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+                                } else {
+                                    result.extend_from_slice("\u{fffd}".as_bytes());
+                                    code = low;
+                                }
+                            }
+
+                            let c = char::from_u32(code).unwrap_or('\u{fffd}');
+                            let mut buffer = [0u8; 4];
+                            result.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+                        }
+                        _ => return Err(format!("Invalid escape symbol at position {}", self.pos)),
+                    }
+                }
+                _ => result.push(byte),
+            }
+        }
+
+        String::from_utf8(result).map_err(|err| format!("Invalid utf8 string: {}", err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(json: &str) -> String {
+        let mut msgpack = Vec::new();
+        json_to_msgpack(json.as_bytes(), &mut msgpack).unwrap();
+
+        let mut result = Vec::new();
+        msgpack_to_json(msgpack.as_slice(), &mut result).unwrap();
+        String::from_utf8(result).unwrap()
+    }
+
+    #[test]
+    fn test_varint_framing() {
+        let mut data = Vec::new();
+        write_varint_length(3, &mut data);
+        data.extend_from_slice(&[1, 2, 3]);
+
+        let long_message = vec![7u8; 300];
+        write_varint_length(long_message.len(), &mut data);
+        data.extend_from_slice(long_message.as_slice());
+
+        assert_eq!(data[4..6], [0xac, 0x02]);
+
+        let frames = split_binary_frames(data.as_slice()).unwrap();
+
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0], &[1, 2, 3]);
+        assert_eq!(frames[1].len(), 300);
+    }
+
+    #[test]
+    fn test_incomplete_frame() {
+        assert!(split_binary_frames(&[5, 1, 2]).is_err());
+    }
+
+    #[test]
+    fn test_ints_encoding() {
+        let mut out = Vec::new();
+        write_int(1, &mut out);
+        write_int(-1, &mut out);
+        write_int(200, &mut out);
+        write_int(-200, &mut out);
+
+        assert_eq!(out, vec![0x01, 0xff, 0xcc, 200, 0xd1, 0xff, 0x38]);
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        assert_eq!(
+            round_trip("[1,-2,300,\"abc\",true,null]"),
+            "[1,-2,300,\"abc\",true,null]"
+        );
+        assert_eq!(
+            round_trip("{\"a\": {\"b\": [1.5]}}"),
+            "{\"a\":{\"b\":[1.5]}}"
+        );
+        assert_eq!(round_trip("\"line\\n\\u00e9\""), "\"line\\né\"");
+    }
+
+    #[test]
+    fn test_invocation_to_json() {
+        let mut payload = Vec::new();
+        write_array_len(6, &mut payload);
+        write_int(1, &mut payload);
+        write_map_len(0, &mut payload);
+        write_str("12", &mut payload);
+        write_str("Send", &mut payload);
+        write_array_len(2, &mut payload);
+        write_str("Hello", &mut payload);
+        write_int(5, &mut payload);
+        write_array_len(1, &mut payload);
+        write_str("1", &mut payload);
+
+        let json = msgpack_message_to_json(payload.as_slice()).unwrap();
+
+        assert_eq!(
+            json,
+            "{\"type\":1,\"invocationId\":\"12\",\"target\":\"Send\",\"arguments\":[\"Hello\",5],\"streamIds\":[\"1\"]}"
+        );
+    }
+
+    #[test]
+    fn test_completion_to_json() {
+        let mut payload = Vec::new();
+        write_array_len(5, &mut payload);
+        write_int(3, &mut payload);
+        write_map_len(0, &mut payload);
+        write_str("7", &mut payload);
+        write_int(3, &mut payload);
+        write_int(42, &mut payload);
+
+        let json = msgpack_message_to_json(payload.as_slice()).unwrap();
+
+        assert_eq!(json, "{\"type\":3,\"invocationId\":\"7\",\"result\":42}");
+    }
+
+    #[test]
+    fn test_nesting_depth_is_limited() {
+        let payload = vec![0x91u8; 100_000];
+
+        let mut result = Vec::new();
+        assert!(msgpack_to_json(payload.as_slice(), &mut result).is_err());
+
+        let json = "[".repeat(100_000);
+        let mut result = Vec::new();
+        assert!(json_to_msgpack(json.as_bytes(), &mut result).is_err());
+    }
+
+    #[test]
+    fn test_ext_types() {
+        let mut result = Vec::new();
+        msgpack_to_json(&[0xd6, 0xff, 0x65, 0x92, 0x00, 0x80], &mut result).unwrap();
+        assert_eq!(
+            "\"2024-01-01T00:00:00Z\"",
+            String::from_utf8(result).unwrap()
+        );
+
+        let mut result = Vec::new();
+        msgpack_to_json(
+            &[
+                0xc7, 0x0c, 0xff, 0x1d, 0xcd, 0x65, 0x00, 0x00, 0x00, 0x00, 0x00, 0x65, 0x92, 0x00,
+                0x80,
+            ],
+            &mut result,
+        )
+        .unwrap();
+        assert_eq!(
+            "\"2024-01-01T00:00:00.5Z\"",
+            String::from_utf8(result).unwrap()
+        );
+
+        let mut result = Vec::new();
+        assert!(msgpack_to_json(&[0xd4, 0x01, 0x00], &mut result).is_err());
+    }
+
+    #[test]
+    fn test_invalid_surrogate_pair() {
+        assert_eq!(round_trip("\"\\ud83d\\u0041\""), "\"\u{fffd}A\"");
+        assert_eq!(round_trip("\"\\ud83d\\ude00\""), "\"\u{1f600}\"");
+    }
+}
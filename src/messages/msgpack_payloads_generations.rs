@@ -0,0 +1,157 @@
+use crate::SignalRParam;
+
+use super::msgpack::{
+    json_to_msgpack, write_array_len, write_bool, write_int, write_map_len, write_nil, write_str,
+    write_uint, write_varint_length,
+};
+
+pub fn generate_msgpack_invocation_payload<'s>(
+    invocation_id: Option<&str>,
+    action_name: &str,
+    parameters: &[SignalRParam<'s>],
+) -> Result<Vec<u8>, String> {
+    let mut message = Vec::new();
+
+    write_array_len(5, &mut message);
+    write_int(1, &mut message);
+    write_map_len(0, &mut message);
+
+    match invocation_id {
+        Some(invocation_id) => write_str(invocation_id, &mut message),
+        None => write_nil(&mut message),
+    }
+
+    write_str(action_name, &mut message);
+    SignalRParam::write_all_msgpack_into(parameters, &mut message)?;
+
+    Ok(compile_frame(message))
+}
+
+pub fn generate_msgpack_completion_payload(
+    invocation_id: &str,
+    result: &Result<Vec<Vec<u8>>, String>,
+) -> Vec<u8> {
+    let mut message = Vec::new();
+
+    match result {
+        Ok(result) => {
+            if result.is_empty() {
+                write_array_len(4, &mut message);
+                write_int(3, &mut message);
+                write_map_len(0, &mut message);
+                write_str(invocation_id, &mut message);
+                write_int(2, &mut message);
+            } else {
+                write_array_len(5, &mut message);
+                write_int(3, &mut message);
+                write_map_len(0, &mut message);
+                write_str(invocation_id, &mut message);
+                write_int(3, &mut message);
+
+                if let Err(err) = write_value(&mut message, result) {
+                    return generate_msgpack_completion_payload(invocation_id, &Err(err));
+                }
+            }
+        }
+        Err(err) => {
+            write_array_len(5, &mut message);
+            write_int(3, &mut message);
+            write_map_len(0, &mut message);
+            write_str(invocation_id, &mut message);
+            write_int(1, &mut message);
+            write_str(err, &mut message);
+        }
+    }
+
+    compile_frame(message)
+}
+
+pub fn generate_msgpack_stream_item_payload(
+    invocation_id: &str,
+    item: &[Vec<u8>],
+) -> Result<Vec<u8>, String> {
+    let mut message = Vec::new();
+
+    write_array_len(4, &mut message);
+    write_int(2, &mut message);
+    write_map_len(0, &mut message);
+    write_str(invocation_id, &mut message);
+    write_value(&mut message, item)?;
+
+    Ok(compile_frame(message))
+}
+
+pub fn generate_msgpack_ping_payload() -> Vec<u8> {
+    let mut message = Vec::new();
+
+    write_array_len(1, &mut message);
+    write_int(6, &mut message);
+
+    compile_frame(message)
+}
+
+pub fn generate_msgpack_ack_payload(sequence_id: u64) -> Vec<u8> {
+    let mut message = Vec::new();
+
+    write_array_len(2, &mut message);
+    write_int(8, &mut message);
+    write_uint(sequence_id, &mut message);
+
+    compile_frame(message)
+}
+
+pub fn generate_msgpack_sequence_payload(sequence_id: u64) -> Vec<u8> {
+    let mut message = Vec::new();
+
+    write_array_len(2, &mut message);
+    write_int(9, &mut message);
+    write_uint(sequence_id, &mut message);
+
+    compile_frame(message)
+}
+
+pub fn generate_msgpack_close_payload(error: Option<&str>, allow_reconnect: bool) -> Vec<u8> {
+    let mut message = Vec::new();
+
+    write_array_len(3, &mut message);
+    write_int(7, &mut message);
+
+    match error {
+        Some(error) => write_str(error, &mut message),
+        None => write_nil(&mut message),
+    }
+
+    write_bool(allow_reconnect, &mut message);
+
+    compile_frame(message)
+}
+
+fn compile_frame(message: Vec<u8>) -> Vec<u8> {
+    let mut result = Vec::with_capacity(message.len() + 5);
+    write_varint_length(message.len(), &mut result);
+    result.extend_from_slice(message.as_slice());
+    result
+}
+
+pub fn write_json_as_msgpack(json: &[u8], out: &mut Vec<u8>) -> Result<(), String> {
+    let mut value = Vec::new();
+    json_to_msgpack(json, &mut value)
+        .map_err(|err| format!("Can not convert json payload to MessagePack: {}", err))?;
+    out.extend_from_slice(value.as_slice());
+    Ok(())
+}
+
+fn write_value(message: &mut Vec<u8>, value: &[Vec<u8>]) -> Result<(), String> {
+    match value.len() {
+        0 => write_nil(message),
+        1 => write_json_as_msgpack(value[0].as_slice(), message)?,
+        _ => {
+            write_array_len(value.len(), message);
+            for item in value {
+                write_json_as_msgpack(item.as_slice(), message)?;
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -1,7 +1,11 @@
+use crate::{SignalRParam, SignalrTransferFormat, SignalrTransport};
+
 pub fn generate_negotiate_response(
     negotiate_version: usize,
     connection_id: &str,
     connection_token: &Option<String>,
+    use_stateful_reconnect: bool,
+    available_transports: &[(SignalrTransport, Vec<SignalrTransferFormat>)],
 ) -> String {
     let mut result = String::new();
 
@@ -20,13 +24,169 @@ pub fn generate_negotiate_response(
         result.push_str("\"")
     }
 
-    result.push_str(
-        ",\"availableTransports\":[{\"transport\":\"WebSockets\",\"transferFormats\":[\"Text\", \"Binary\"]},{\"transport\":\"ServerSentEvents\",\"transferFormats\":[\"Text\"]},{\"transport\":\"LongPolling\",\"transferFormats\":[\"Text\",\"Binary\"]}]}",
-    );
+    if use_stateful_reconnect {
+        result.push_str(",\"useStatefulReconnect\":true");
+    }
+
+    result.push_str(",\"availableTransports\":[");
+
+    for (index, (transport, transfer_formats)) in available_transports.iter().enumerate() {
+        if index > 0 {
+            result.push(',');
+        }
+
+        result.push_str("{\"transport\":\"");
+        result.push_str(transport.as_str());
+        result.push_str("\",\"transferFormats\":[");
+
+        for (index, transfer_format) in transfer_formats.iter().enumerate() {
+            if index > 0 {
+                result.push(',');
+            }
+
+            result.push('"');
+            result.push_str(transfer_format.as_str());
+            result.push('"');
+        }
+
+        result.push_str("]}");
+    }
+
+    result.push_str("]}");
 
     result
 }
 
+pub fn generate_handshake_response(error: Option<&str>) -> String {
+    match error {
+        Some(error) => {
+            let json_string = my_json::EscapedJsonString::new(error);
+            format!("{{\"error\":\"{}\"}}", json_string.as_str())
+        }
+        None => "{}".to_string(),
+    }
+}
+
+pub fn generate_close_payload(error: Option<&str>, allow_reconnect: bool) -> Vec<u8> {
+    let mut payload = Vec::new();
+
+    payload.extend_from_slice("{\"type\":7".as_bytes());
+
+    if let Some(error) = error {
+        let json_string = my_json::EscapedJsonString::new(error);
+        payload.extend_from_slice(",\"error\":\"".as_bytes());
+        payload.extend_from_slice(json_string.as_str().as_bytes());
+        payload.push(b'"');
+    }
+
+    if allow_reconnect {
+        payload.extend_from_slice(",\"allowReconnect\":true".as_bytes());
+    }
+
+    payload.extend_from_slice("}".as_bytes());
+    payload.push(30);
+
+    payload
+}
+
 pub fn get_ping_payload() -> &'static str {
     "{\"type\":6}"
 }
+
+pub fn generate_ack_payload(sequence_id: u64) -> Vec<u8> {
+    let mut payload = format!("{{\"type\":8,\"sequenceId\":{}}}", sequence_id).into_bytes();
+    payload.push(30);
+    payload
+}
+
+pub fn generate_sequence_payload(sequence_id: u64) -> Vec<u8> {
+    let mut payload = format!("{{\"type\":9,\"sequenceId\":{}}}", sequence_id).into_bytes();
+    payload.push(30);
+    payload
+}
+
+pub fn generate_invocation_payload<'s>(
+    invocation_id: Option<&str>,
+    action_name: &str,
+    parameters: &[SignalRParam<'s>],
+) -> Vec<u8> {
+    let mut result = Vec::new();
+
+    result.extend_from_slice("{\"type\":1,".as_bytes());
+
+    if let Some(invocation_id) = invocation_id {
+        result.extend_from_slice("\"invocationId\":\"".as_bytes());
+        result.extend_from_slice(invocation_id.as_bytes());
+        result.extend_from_slice("\",".as_bytes());
+    }
+
+    result.extend_from_slice("\"target\":\"".as_bytes());
+    result.extend_from_slice(action_name.as_bytes());
+    result.extend_from_slice("\",\"arguments\":[".as_bytes());
+    SignalRParam::write_all_into(parameters, &mut result);
+    result.extend_from_slice("]}".as_bytes());
+    result.push(30);
+
+    result
+}
+
+pub fn generate_completion_payload(
+    invocation_id: &str,
+    result: &Result<Vec<Vec<u8>>, String>,
+) -> Vec<u8> {
+    let mut payload = Vec::new();
+
+    payload.extend_from_slice("{\"type\":3,\"invocationId\":\"".as_bytes());
+    payload.extend_from_slice(invocation_id.as_bytes());
+    payload.push(b'"');
+
+    match result {
+        Ok(result) => {
+            if !result.is_empty() {
+                payload.extend_from_slice(",\"result\":".as_bytes());
+                write_value(&mut payload, result);
+            }
+        }
+        Err(err) => {
+            let json_string = my_json::EscapedJsonString::new(err);
+            payload.extend_from_slice(",\"error\":\"".as_bytes());
+            payload.extend_from_slice(json_string.as_str().as_bytes());
+            payload.push(b'"');
+        }
+    }
+
+    payload.extend_from_slice("}".as_bytes());
+    payload.push(30);
+
+    payload
+}
+
+pub fn generate_stream_item_payload(invocation_id: &str, item: &[Vec<u8>]) -> Vec<u8> {
+    let mut payload = Vec::new();
+
+    payload.extend_from_slice("{\"type\":2,\"invocationId\":\"".as_bytes());
+    payload.extend_from_slice(invocation_id.as_bytes());
+    payload.extend_from_slice("\",\"item\":".as_bytes());
+    write_value(&mut payload, item);
+    payload.extend_from_slice("}".as_bytes());
+    payload.push(30);
+
+    payload
+}
+
+fn write_value(payload: &mut Vec<u8>, value: &[Vec<u8>]) {
+    match value.len() {
+        0 => payload.extend_from_slice("null".as_bytes()),
+        1 => payload.extend_from_slice(value[0].as_slice()),
+        _ => {
+            payload.push(b'[');
+            for (index, item) in value.iter().enumerate() {
+                if index > 0 {
+                    payload.push(b',');
+                }
+                payload.extend_from_slice(item.as_slice());
+            }
+            payload.push(b']');
+        }
+    }
+}
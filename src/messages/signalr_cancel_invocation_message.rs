@@ -0,0 +1,42 @@
+use my_json::json_reader::JsonFirstLineReader;
+
+pub struct SignalrCancelInvocationMessage<'s> {
+    pub invocation_id: &'s str,
+}
+
+impl<'s> SignalrCancelInvocationMessage<'s> {
+    pub fn parse(payload: &'s str) -> Option<Self> {
+        let json_reader = JsonFirstLineReader::new(payload.as_bytes());
+        for line in json_reader {
+            let line = line.ok()?;
+
+            if line.get_name().ok()? == "invocationId" {
+                let value = line.get_value().ok()?;
+                return Some(Self {
+                    invocation_id: value.as_str()?,
+                });
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SignalrCancelInvocationMessage;
+
+    #[test]
+    fn test_parse() {
+        let message =
+            SignalrCancelInvocationMessage::parse("{\"type\":5,\"invocationId\":\"7\"}").unwrap();
+
+        assert_eq!(message.invocation_id, "7");
+    }
+
+    #[test]
+    fn test_parse_malformed_payload() {
+        assert!(SignalrCancelInvocationMessage::parse("{\"type\":5,\"invocationId\"").is_none());
+        assert!(SignalrCancelInvocationMessage::parse("not a json").is_none());
+    }
+}
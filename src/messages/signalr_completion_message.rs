@@ -0,0 +1,64 @@
+use my_json::json_reader::JsonFirstLineReader;
+
+pub struct SignalrCompletionMessage<'s> {
+    pub invocation_id: &'s str,
+    pub result: Option<&'s [u8]>,
+    pub error: Option<&'s str>,
+}
+
+impl<'s> SignalrCompletionMessage<'s> {
+    pub fn parse(payload: &'s str) -> Option<Self> {
+        let mut invocation_id = None;
+        let mut result = None;
+        let mut error = None;
+
+        let json_reader = JsonFirstLineReader::new(payload.as_bytes());
+        for line in json_reader {
+            let line = line.ok()?;
+
+            match line.get_name().ok()? {
+                "invocationId" => {
+                    let value = line.get_value().ok()?;
+                    invocation_id = value.as_str();
+                }
+                "result" => {
+                    let value = line.get_value().ok()?;
+                    result = value.as_bytes();
+                }
+                "error" => {
+                    let value = line.get_value().ok()?;
+                    error = value.as_str();
+                }
+                _ => {}
+            }
+        }
+
+        Some(Self {
+            invocation_id: invocation_id?,
+            result,
+            error,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SignalrCompletionMessage;
+
+    #[test]
+    fn test_parse() {
+        let message =
+            SignalrCompletionMessage::parse("{\"type\":3,\"invocationId\":\"1\",\"result\":42}")
+                .unwrap();
+
+        assert_eq!(message.invocation_id, "1");
+        assert_eq!(message.result, Some("42".as_bytes()));
+        assert!(message.error.is_none());
+    }
+
+    #[test]
+    fn test_parse_malformed_payload() {
+        assert!(SignalrCompletionMessage::parse("{\"type\":3,\"invocationId\"").is_none());
+        assert!(SignalrCompletionMessage::parse("not a json").is_none());
+    }
+}
@@ -0,0 +1,73 @@
+use my_json::json_reader::JsonFirstLineReader;
+
+use super::HubProtocol;
+
+pub struct SignalrHandshakeRequest<'s> {
+    pub protocol: Option<&'s str>,
+    pub version: Option<usize>,
+}
+
+impl<'s> SignalrHandshakeRequest<'s> {
+    pub fn parse(payload: &'s [u8]) -> Result<Self, String> {
+        let mut protocol = None;
+        let mut version = None;
+
+        let json_reader = JsonFirstLineReader::new(payload);
+        for line in json_reader {
+            let line = line.map_err(|err| format!("Invalid handshake payload: {:?}", err))?;
+
+            let name = line
+                .get_name()
+                .map_err(|err| format!("Invalid handshake payload: {:?}", err))?;
+
+            match name {
+                "protocol" => {
+                    let value = line
+                        .get_value()
+                        .map_err(|err| format!("Invalid handshake payload: {:?}", err))?;
+                    protocol = value.as_str();
+                }
+                "version" => {
+                    let value = line
+                        .get_value()
+                        .map_err(|err| format!("Invalid handshake payload: {:?}", err))?;
+                    version = value.as_str().and_then(|value| value.parse::<usize>().ok());
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Self { protocol, version })
+    }
+
+    pub fn get_hub_protocol(&self) -> Result<HubProtocol, String> {
+        let protocol_name = match self.protocol {
+            Some(protocol_name) => protocol_name,
+            None => return Err("The 'protocol' property is missing in handshake".to_string()),
+        };
+
+        let version = match self.version {
+            Some(version) => version,
+            None => return Err("The 'version' property is missing in handshake".to_string()),
+        };
+
+        let protocol = match HubProtocol::from_name(protocol_name) {
+            Some(protocol) => protocol,
+            None => {
+                return Err(format!(
+                    "The protocol '{}' is not supported.",
+                    protocol_name
+                ))
+            }
+        };
+
+        if !protocol.is_version_supported(version) {
+            return Err(format!(
+                "The server does not support version {} of the '{}' protocol.",
+                version, protocol_name
+            ));
+        }
+
+        Ok(protocol)
+    }
+}
@@ -1,12 +1,13 @@
 use std::collections::HashMap;
 
-use my_json::json_reader::JsonFirstLineReader;
+use my_json::json_reader::{array_parser::JsonArrayIterator, JsonFirstLineReader};
 
 pub struct SignalrMessage<'s> {
     pub headers: Option<HashMap<String, String>>,
     pub invocation_id: Option<&'s str>,
     pub target: &'s str,
     pub arguments: &'s [u8],
+    pub stream_ids: Vec<&'s str>,
 }
 
 impl<'s> SignalrMessage<'s> {
@@ -14,6 +15,7 @@ impl<'s> SignalrMessage<'s> {
         let mut invocation_id = None;
         let mut target = None;
         let mut arguments = None;
+        let mut stream_ids = Vec::new();
 
         let json_reader = JsonFirstLineReader::new(payload.as_bytes());
         for line in json_reader {
@@ -32,6 +34,15 @@ impl<'s> SignalrMessage<'s> {
                     let result = line.get_value().unwrap();
                     target = result.as_str();
                 }
+                "streamIds" => {
+                    let result = line.get_value().unwrap();
+                    if let Some(value) = result.as_bytes() {
+                        for item in JsonArrayIterator::new(value) {
+                            let item = std::str::from_utf8(item.unwrap()).unwrap();
+                            stream_ids.push(item.trim_matches('"'));
+                        }
+                    }
+                }
                 _ => {}
             }
         }
@@ -48,6 +59,7 @@ impl<'s> SignalrMessage<'s> {
             invocation_id: invocation_id,
             target: target.unwrap(),
             arguments: arguments.unwrap(),
+            stream_ids,
         }
     }
 }
@@ -0,0 +1,42 @@
+use my_json::json_reader::JsonFirstLineReader;
+
+pub struct SignalrSequenceMessage {
+    pub sequence_id: u64,
+}
+
+impl SignalrSequenceMessage {
+    pub fn parse(payload: &str) -> Option<Self> {
+        let json_reader = JsonFirstLineReader::new(payload.as_bytes());
+        for line in json_reader {
+            let line = line.ok()?;
+
+            if line.get_name().ok()? == "sequenceId" {
+                let value = line.get_value().ok()?;
+                return Some(Self {
+                    sequence_id: value.as_str()?.parse().ok()?,
+                });
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SignalrSequenceMessage;
+
+    #[test]
+    fn test_parse() {
+        let message = SignalrSequenceMessage::parse("{\"type\":9,\"sequenceId\":12}").unwrap();
+
+        assert_eq!(message.sequence_id, 12);
+    }
+
+    #[test]
+    fn test_parse_malformed_payload() {
+        assert!(SignalrSequenceMessage::parse("{\"type\":9,\"sequenceId\"").is_none());
+        assert!(SignalrSequenceMessage::parse("{\"type\":9,\"sequenceId\":\"a\"}").is_none());
+        assert!(SignalrSequenceMessage::parse("not a json").is_none());
+    }
+}
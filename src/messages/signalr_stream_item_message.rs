@@ -0,0 +1,60 @@
+use my_json::json_reader::JsonFirstLineReader;
+
+pub struct SignalrStreamItemMessage<'s> {
+    pub invocation_id: &'s str,
+    pub item: &'s [u8],
+}
+
+impl<'s> SignalrStreamItemMessage<'s> {
+    pub fn parse(payload: &'s str) -> Option<Self> {
+        let mut invocation_id = None;
+        let mut item = None;
+
+        let json_reader = JsonFirstLineReader::new(payload.as_bytes());
+        for line in json_reader {
+            let line = line.ok()?;
+
+            match line.get_name().ok()? {
+                "invocationId" => {
+                    let value = line.get_value().ok()?;
+                    invocation_id = value.as_str();
+                }
+                "item" => {
+                    let value = line.get_value().ok()?;
+                    item = value.as_bytes();
+                }
+                _ => {}
+            }
+        }
+
+        Some(Self {
+            invocation_id: invocation_id?,
+            item: item?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SignalrStreamItemMessage;
+
+    #[test]
+    fn test_parse() {
+        let message = SignalrStreamItemMessage::parse(
+            "{\"type\":2,\"invocationId\":\"3\",\"item\":{\"a\":1}}",
+        )
+        .unwrap();
+
+        assert_eq!(message.invocation_id, "3");
+        assert_eq!(message.item, "{\"a\":1}".as_bytes());
+    }
+
+    #[test]
+    fn test_parse_malformed_payload() {
+        assert!(
+            SignalrStreamItemMessage::parse("{\"type\":2,\"invocationId\":\"3\",\"item\"")
+                .is_none()
+        );
+        assert!(SignalrStreamItemMessage::parse("not a json").is_none());
+    }
+}
@@ -0,0 +1,66 @@
+pub const RECORD_SEPARATOR: char = '\u{1e}';
+
+pub fn split_text_frames(payload: &str) -> Vec<&str> {
+    payload
+        .split(RECORD_SEPARATOR)
+        .filter(|message| !message.trim().is_empty())
+        .collect()
+}
+
+pub fn generate_server_sent_event(payload: &[u8]) -> Vec<u8> {
+    let mut result = Vec::with_capacity(payload.len() + 16);
+
+    for line in payload.split(|b| *b == b'\n') {
+        result.extend_from_slice("data: ".as_bytes());
+        result.extend_from_slice(line.strip_suffix(b"\r").unwrap_or(line));
+        result.extend_from_slice("\r\n".as_bytes());
+    }
+
+    result.extend_from_slice("\r\n".as_bytes());
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_single_message() {
+        let frames = split_text_frames("{\"type\":6}\u{1e}");
+
+        assert_eq!(frames, vec!["{\"type\":6}"]);
+    }
+
+    #[test]
+    fn test_several_messages() {
+        let frames = split_text_frames(
+            "{\"type\":6}\u{1e}{\"type\":1,\"target\":\"Send\",\"arguments\":[]}\u{1e}",
+        );
+
+        assert_eq!(
+            frames,
+            vec![
+                "{\"type\":6}",
+                "{\"type\":1,\"target\":\"Send\",\"arguments\":[]}"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_server_sent_event_with_several_lines() {
+        let event = generate_server_sent_event("{\"type\":6}\n\u{1e}".as_bytes());
+
+        assert_eq!(
+            String::from_utf8(event).unwrap(),
+            "data: {\"type\":6}\r\ndata: \u{1e}\r\n\r\n"
+        );
+    }
+
+    #[test]
+    fn test_message_without_separator() {
+        let frames = split_text_frames("{\"type\":6}");
+
+        assert_eq!(frames, vec!["{\"type\":6}"]);
+    }
+}
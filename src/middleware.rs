@@ -1,6 +1,7 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
-use hyper::Method;
+use futures::StreamExt;
+use hyper::{Method, StatusCode};
 use my_http_server::{
     HttpContext, HttpFailResult, HttpOkResult, HttpOutput, HttpPath, HttpServerMiddleware,
     HttpServerRequestFlow, RequestData, WebContentType,
@@ -9,11 +10,16 @@ use rust_extensions::Logger;
 use tokio::sync::Mutex;
 
 use crate::{
-    my_signal_r_actions::MySignalrActions, MiddlewareBuilder, SignalrConnectionsList,
+    messages::HubProtocol, my_signal_r_actions::MySignalrActions, MiddlewareBuilder,
+    MySignalrAuthenticator, MySignalrConnection, MySignalrMiddlewareSettings,
+    OutboundQueueSettings, SignalrConnectionSettings, SignalrConnectionsList, SignalrCtxFactory,
+    SignalrIdentity, SignalrTransferFormat, SignalrTransport, StatefulReconnectSettings,
     WebSocketCallbacks,
 };
 
-pub struct MySignalrMiddleware<TCtx: Send + Sync + Default + 'static> {
+const LONG_POOLING_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(90);
+
+pub struct MySignalrMiddleware<TCtx: Send + Sync + 'static> {
     pub hub_name: String,
     negotiate_path: HttpPath,
     socket_id: Mutex<i64>,
@@ -21,6 +27,12 @@ pub struct MySignalrMiddleware<TCtx: Send + Sync + Default + 'static> {
     signalr_list: Arc<SignalrConnectionsList<TCtx>>,
     actions: Arc<MySignalrActions<TCtx>>,
     disconnect_timeout: std::time::Duration,
+    stateful_reconnect: Option<StatefulReconnectSettings>,
+    outbound_queue: Option<OutboundQueueSettings>,
+    transports: Vec<SignalrTransport>,
+    transfer_formats: Vec<SignalrTransferFormat>,
+    authenticator: Option<Arc<dyn MySignalrAuthenticator + Send + Sync + 'static>>,
+    ctx_factory: SignalrCtxFactory<TCtx>,
 }
 
 impl<TCtx: Send + Sync + Default + 'static> MySignalrMiddleware<TCtx> {
@@ -31,16 +43,35 @@ impl<TCtx: Send + Sync + Default + 'static> MySignalrMiddleware<TCtx> {
     ) -> MiddlewareBuilder<TCtx> {
         MiddlewareBuilder::new(hub_name.to_string(), signalr_list, logger)
     }
+}
+
+impl<TCtx: Send + Sync + 'static> MySignalrMiddleware<TCtx> {
+    pub fn new_with_ctx_factory(
+        hub_name: &str,
+        signalr_list: Arc<SignalrConnectionsList<TCtx>>,
+        logger: Arc<dyn Logger + Send + Sync + 'static>,
+        ctx_factory: impl Fn(&HttpContext) -> Result<TCtx, HttpFailResult> + Send + Sync + 'static,
+    ) -> MiddlewareBuilder<TCtx> {
+        MiddlewareBuilder::new_with_ctx_factory(
+            hub_name.to_string(),
+            signalr_list,
+            logger,
+            ctx_factory,
+        )
+    }
 
+    /// `signalr_list` belongs to one hub: `SignalrConnectionsList::close` fires the callbacks
+    /// of the first middleware it was passed to.
     pub fn new(
         hub_name: &str,
         signalr_list: Arc<SignalrConnectionsList<TCtx>>,
         actions: MySignalrActions<TCtx>,
-        disconnect_timeout: std::time::Duration,
+        settings: MySignalrMiddlewareSettings<TCtx>,
     ) -> Self {
         let hub_name = hub_name.to_lowercase();
 
         let actions = Arc::new(actions);
+        signalr_list.set_callbacks(actions.clone());
 
         Self {
             negotiate_path: compile_negotiate_uri(hub_name.as_str()),
@@ -49,10 +80,21 @@ impl<TCtx: Send + Sync + Default + 'static> MySignalrMiddleware<TCtx> {
             web_socket_callback: Arc::new(WebSocketCallbacks {
                 signalr_list,
                 my_signal_r_callbacks: actions.clone(),
+                keep_alive_interval: settings.keep_alive_interval,
+                transfer_formats: settings.transfer_formats.clone(),
+                allow_skip_negotiation: settings.allow_skip_negotiation,
+                outbound_queue: settings.outbound_queue,
+                pending_web_sockets: Mutex::new(HashMap::new()),
             }),
             socket_id: Mutex::new(0),
             actions,
-            disconnect_timeout,
+            disconnect_timeout: settings.disconnect_timeout,
+            stateful_reconnect: settings.stateful_reconnect,
+            outbound_queue: settings.outbound_queue,
+            transports: settings.transports,
+            transfer_formats: settings.transfer_formats,
+            authenticator: settings.authenticator,
+            ctx_factory: settings.ctx_factory,
         }
     }
 
@@ -70,20 +112,78 @@ impl<TCtx: Send + Sync + Default + 'static> MySignalrMiddleware<TCtx> {
         println!("handle_negotiate_request");
         let query_string_result = ctx.request.get_query_string();
 
+        let mut use_stateful_reconnect = false;
+        let mut requested_transports = SignalrTransport::ALL.to_vec();
+
         let negotiation_version = match query_string_result {
             Ok(value) => {
-                if let Some(result) = value.get_optional("negotiateVersion") {
-                    result.value.parse::<usize>().unwrap()
-                } else {
-                    0
+                if let Some(result) = value.get_optional("useStatefulReconnect") {
+                    use_stateful_reconnect = result.value.eq_ignore_ascii_case("true");
+                }
+
+                if let Some(result) = value.get_optional("transport") {
+                    requested_transports = SignalrTransport::parse_hint(result.value);
+                }
+
+                match value.get_optional("negotiateVersion") {
+                    Some(result) => match result.value.parse::<usize>() {
+                        Ok(negotiation_version) => negotiation_version,
+                        Err(_) => {
+                            return Err(HttpFailResult::as_validation_error(format!(
+                                "Invalid negotiateVersion: {}",
+                                result.value
+                            )))
+                        }
+                    },
+                    None => 0,
                 }
             }
             Err(_) => 0,
         };
 
-        let (_, response) =
-            crate::process_connect(&self.actions, &self.signalr_list, negotiation_version, None)
-                .await;
+        let available_transports = self.get_available_transports(&requested_transports);
+
+        let stateful_reconnect = if use_stateful_reconnect {
+            self.stateful_reconnect
+        } else {
+            None
+        };
+
+        let identity = self.authenticate(ctx).await?;
+        let signalr_ctx = (self.ctx_factory)(ctx)?;
+
+        let signalr_connection = crate::process_connect(
+            &self.actions,
+            &self.signalr_list,
+            None,
+            SignalrConnectionSettings {
+                negotiation_version,
+                stateful_reconnect,
+                outbound_queue: self.outbound_queue,
+            },
+            identity,
+            signalr_ctx,
+        )
+        .await?;
+
+        let response = crate::messages::generate_negotiate_response(
+            negotiation_version,
+            signalr_connection.connection_id.as_str(),
+            &signalr_connection.connection_token,
+            signalr_connection.stateful_reconnect.is_some(),
+            &available_transports,
+        );
+
+        if signalr_connection.try_start_liveness_loop() {
+            tokio::spawn(crate::signalr_liveness_loop::start(
+                self.web_socket_callback.my_signal_r_callbacks.clone(),
+                self.signalr_list.clone(),
+                signalr_connection,
+                self.disconnect_timeout,
+                self.web_socket_callback.keep_alive_interval,
+            ));
+        }
+
         HttpOutput::Content {
             headers: None,
             content_type: Some(WebContentType::Text),
@@ -92,10 +192,248 @@ impl<TCtx: Send + Sync + Default + 'static> MySignalrMiddleware<TCtx> {
         .into_ok_result(true)
         .into()
     }
+
+    async fn authenticate(
+        &self,
+        ctx: &HttpContext,
+    ) -> Result<Option<SignalrIdentity>, HttpFailResult> {
+        match self.authenticator.as_ref() {
+            Some(authenticator) => {
+                let identity = authenticator.authenticate(ctx).await?;
+                Ok(Some(identity))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn get_available_transports(
+        &self,
+        requested_transports: &[SignalrTransport],
+    ) -> Vec<(SignalrTransport, Vec<SignalrTransferFormat>)> {
+        let mut result = Vec::new();
+
+        for transport in &self.transports {
+            if !requested_transports.contains(transport) {
+                continue;
+            }
+
+            let transfer_formats: Vec<SignalrTransferFormat> = transport
+                .get_supported_transfer_formats()
+                .iter()
+                .filter(|transfer_format| self.transfer_formats.contains(transfer_format))
+                .copied()
+                .collect();
+
+            if !transfer_formats.is_empty() {
+                result.push((*transport, transfer_formats));
+            }
+        }
+
+        result
+    }
+
+    fn check_transport_is_enabled(
+        &self,
+        transport: SignalrTransport,
+    ) -> Result<(), HttpFailResult> {
+        if self.transports.contains(&transport) {
+            return Ok(());
+        }
+
+        Err(HttpFailResult::as_not_found(
+            format!("{} transport is disabled", transport.as_str()),
+            false,
+        ))
+    }
+
+    fn check_http_transport_is_enabled(&self) -> Result<(), HttpFailResult> {
+        if self
+            .transports
+            .iter()
+            .any(|transport| *transport != SignalrTransport::WebSocket)
+        {
+            return Ok(());
+        }
+
+        Err(HttpFailResult::as_not_found(
+            "Http transports are disabled".to_string(),
+            false,
+        ))
+    }
+
+    async fn get_connection_by_id(
+        &self,
+        ctx: &HttpContext,
+        identity: Option<&SignalrIdentity>,
+    ) -> Result<Arc<MySignalrConnection<TCtx>>, HttpFailResult> {
+        let query_string = ctx.request.get_query_string()?;
+
+        let connection_token = match query_string.get_optional("id") {
+            Some(connection_token) => connection_token,
+            None => return Err(HttpFailResult::as_query_parameter_required("id")),
+        };
+
+        match self
+            .signalr_list
+            .get_by_connection_token(connection_token.value)
+            .await
+        {
+            Some(signalr_connection) => {
+                if !is_the_same_user(signalr_connection.identity.as_ref(), identity) {
+                    return Err(HttpFailResult::as_forbidden(Some(
+                        "SignalR connection belongs to another user".to_string(),
+                    )));
+                }
+
+                Ok(signalr_connection)
+            }
+            None => Err(HttpFailResult::as_not_found(
+                format!(
+                    "SignalR with connection_token {} is not found",
+                    connection_token.value
+                ),
+                false,
+            )),
+        }
+    }
+
+    async fn handle_long_pooling_get(
+        &self,
+        ctx: &mut HttpContext,
+    ) -> Result<HttpOkResult, HttpFailResult> {
+        let identity = self.authenticate(ctx).await?;
+        let signalr_connection = self.get_connection_by_id(ctx, identity.as_ref()).await?;
+
+        signalr_connection.update_incoming_activity();
+
+        if signalr_connection.attach_long_pooling().await {
+            return compile_response(StatusCode::OK, "text/plain", vec![]);
+        }
+
+        let result = signalr_connection.long_pool(LONG_POOLING_TIMEOUT).await;
+
+        signalr_connection.update_incoming_activity();
+
+        match result {
+            Some(payload) => {
+                let content_type = match signalr_connection.get_hub_protocol().await {
+                    HubProtocol::Json => "text/plain",
+                    HubProtocol::MessagePack => "application/octet-stream",
+                };
+
+                compile_response(StatusCode::OK, content_type, payload)
+            }
+            None => compile_response(StatusCode::NO_CONTENT, "text/plain", vec![]),
+        }
+    }
+
+    async fn handle_server_sent_events(
+        &self,
+        ctx: &mut HttpContext,
+    ) -> Result<HttpOkResult, HttpFailResult> {
+        let identity = self.authenticate(ctx).await?;
+        let signalr_connection = self.get_connection_by_id(ctx, identity.as_ref()).await?;
+
+        signalr_connection.update_incoming_activity();
+
+        let (sender, mut receiver) = futures::channel::mpsc::unbounded();
+
+        if !signalr_connection.attach_server_sent_events(sender).await {
+            return compile_response(
+                StatusCode::CONFLICT,
+                "text/plain",
+                "Server sent events stream is already open for this connection"
+                    .as_bytes()
+                    .to_vec(),
+            );
+        }
+
+        let (mut body_sender, body) = hyper::Body::channel();
+
+        let signalr_list = self.signalr_list.clone();
+        let callbacks = self.web_socket_callback.my_signal_r_callbacks.clone();
+
+        tokio::spawn(async move {
+            while let Some(event) = receiver.next().await {
+                if body_sender.send_data(event.into()).await.is_err() {
+                    break;
+                }
+            }
+
+            crate::process_disconnect(
+                &signalr_list,
+                signalr_connection.get_list_index(),
+                &callbacks,
+            )
+            .await;
+        });
+
+        let response = hyper::Response::builder()
+            .status(StatusCode::OK)
+            .header(hyper::header::CONTENT_TYPE, "text/event-stream")
+            .header(hyper::header::CACHE_CONTROL, "no-cache")
+            .body(body)
+            .unwrap();
+
+        HttpOutput::Raw(response).into_ok_result(true)
+    }
+
+    async fn handle_post_messages(
+        &self,
+        ctx: &mut HttpContext,
+    ) -> Result<HttpOkResult, HttpFailResult> {
+        let identity = self.authenticate(ctx).await?;
+        let signalr_connection = self.get_connection_by_id(ctx, identity.as_ref()).await?;
+
+        match signalr_connection.get_transport().await {
+            Some(SignalrTransport::WebSocket) | None => {
+                return Err(HttpFailResult::as_validation_error(
+                    "POST requests are allowed only for ServerSentEvents and LongPolling connections"
+                        .to_string(),
+                ))
+            }
+            Some(transport) => self.check_transport_is_enabled(transport)?,
+        }
+
+        let body = match &mut ctx.request.req {
+            RequestData::AsRaw(request) => hyper::body::to_bytes(request.body_mut())
+                .await
+                .map_err(|err| HttpFailResult::as_validation_error(format!("{:?}", err)))?,
+            _ => {
+                return Err(HttpFailResult::as_validation_error(
+                    "Request body is not available".to_string(),
+                ))
+            }
+        };
+
+        self.web_socket_callback
+            .handle_http_payload(&ctx.request.addr, &signalr_connection, body.as_ref())
+            .await
+            .map_err(HttpFailResult::as_validation_error)?;
+
+        compile_response(StatusCode::OK, "text/plain", vec![])
+    }
+
+    async fn handle_long_pooling_delete(
+        &self,
+        ctx: &mut HttpContext,
+    ) -> Result<HttpOkResult, HttpFailResult> {
+        let identity = self.authenticate(ctx).await?;
+        let signalr_connection = self.get_connection_by_id(ctx, identity.as_ref()).await?;
+
+        crate::process_disconnect(
+            &self.signalr_list,
+            signalr_connection.get_list_index(),
+            &self.web_socket_callback.my_signal_r_callbacks,
+        )
+        .await;
+
+        compile_response(StatusCode::ACCEPTED, "text/plain", vec![])
+    }
 }
 
 #[async_trait::async_trait]
-impl<TCtx: Send + Sync + Default + 'static> HttpServerMiddleware for MySignalrMiddleware<TCtx> {
+impl<TCtx: Send + Sync + 'static> HttpServerMiddleware for MySignalrMiddleware<TCtx> {
     async fn handle_request(
         &self,
         ctx: &mut HttpContext,
@@ -114,9 +452,29 @@ impl<TCtx: Send + Sync + Default + 'static> HttpServerMiddleware for MySignalrMi
             .get_optional_header("sec-websocket-key")
             .is_some()
         {
+            self.check_transport_is_enabled(SignalrTransport::WebSocket)?;
+
+            let identity = self.authenticate(ctx).await?;
+
+            let signalr_ctx = if has_connection_id(ctx) {
+                self.get_connection_by_id(ctx, identity.as_ref()).await?;
+                None
+            } else if self.web_socket_callback.allow_skip_negotiation {
+                Some((self.ctx_factory)(ctx)?)
+            } else {
+                None
+            };
+
             if let RequestData::AsRaw(request) = &mut ctx.request.req {
                 let id = self.get_socket_id().await;
-                return my_http_server_web_sockets::handle_web_socket_upgrade(
+
+                if identity.is_some() || signalr_ctx.is_some() {
+                    self.web_socket_callback
+                        .add_pending_web_socket(id, identity, signalr_ctx)
+                        .await;
+                }
+
+                let result = my_http_server_web_sockets::handle_web_socket_upgrade(
                     request,
                     self.web_socket_callback.clone(),
                     id,
@@ -124,6 +482,12 @@ impl<TCtx: Send + Sync + Default + 'static> HttpServerMiddleware for MySignalrMi
                     self.disconnect_timeout,
                 )
                 .await;
+
+                if result.is_err() {
+                    self.web_socket_callback.take_pending_web_socket(id).await;
+                }
+
+                return result;
             }
 
             return get_next.next(ctx).await;
@@ -135,10 +499,77 @@ impl<TCtx: Send + Sync + Default + 'static> HttpServerMiddleware for MySignalrMi
             }
         }
 
+        if ctx.request.http_path.segments_amount() == 1 {
+            if ctx.request.method == Method::GET {
+                if accepts_event_stream(ctx) {
+                    self.check_transport_is_enabled(SignalrTransport::ServerSentEvents)?;
+                    return self.handle_server_sent_events(ctx).await;
+                }
+
+                self.check_transport_is_enabled(SignalrTransport::LongPolling)?;
+                return self.handle_long_pooling_get(ctx).await;
+            }
+
+            if ctx.request.method == Method::POST {
+                self.check_http_transport_is_enabled()?;
+                return self.handle_post_messages(ctx).await;
+            }
+
+            if ctx.request.method == Method::DELETE {
+                self.check_transport_is_enabled(SignalrTransport::LongPolling)?;
+                return self.handle_long_pooling_delete(ctx).await;
+            }
+        }
+
         get_next.next(ctx).await
     }
 }
 
+fn is_the_same_user(
+    connection_identity: Option<&SignalrIdentity>,
+    request_identity: Option<&SignalrIdentity>,
+) -> bool {
+    match (connection_identity, request_identity) {
+        (Some(connection_identity), Some(request_identity)) => {
+            connection_identity.user_id == request_identity.user_id
+        }
+        _ => true,
+    }
+}
+
+fn has_connection_id(ctx: &HttpContext) -> bool {
+    match ctx.request.get_query_string() {
+        Ok(query_string) => query_string.get_optional("id").is_some(),
+        Err(_) => false,
+    }
+}
+
+fn accepts_event_stream(ctx: &HttpContext) -> bool {
+    if let RequestData::AsRaw(request) = &ctx.request.req {
+        if let Some(accept) = request.headers().get(hyper::header::ACCEPT) {
+            if let Ok(accept) = accept.to_str() {
+                return accept.contains("text/event-stream");
+            }
+        }
+    }
+
+    false
+}
+
+fn compile_response(
+    status_code: StatusCode,
+    content_type: &str,
+    content: Vec<u8>,
+) -> Result<HttpOkResult, HttpFailResult> {
+    let response = hyper::Response::builder()
+        .status(status_code)
+        .header(hyper::header::CONTENT_TYPE, content_type)
+        .body(hyper::Body::from(content))
+        .unwrap();
+
+    HttpOutput::Raw(response).into_ok_result(true)
+}
+
 fn compile_negotiate_uri(hub_name: &str) -> HttpPath {
     let mut result = String::new();
 
@@ -168,4 +599,15 @@ mod tests {
 
         assert!(result.has_values_at_index_case_insensitive(0, &["signalr", "negotiate"]));
     }
+
+    #[test]
+    fn test_connection_owner_check() {
+        let owner = crate::SignalrIdentity::new("user-1".to_string());
+        let same_user = crate::SignalrIdentity::new("user-1".to_string());
+        let another_user = crate::SignalrIdentity::new("user-2".to_string());
+
+        assert!(super::is_the_same_user(Some(&owner), Some(&same_user)));
+        assert!(!super::is_the_same_user(Some(&owner), Some(&another_user)));
+        assert!(super::is_the_same_user(None, None));
+    }
 }
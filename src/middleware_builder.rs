@@ -1,18 +1,23 @@
 use std::sync::Arc;
 
+use my_http_server::{HttpContext, HttpFailResult};
 use rust_extensions::Logger;
 
 use crate::{
-    my_signal_r_actions::MySignalrActions, MySignalrActionCallbacks, MySignalrMiddleware,
-    MySignalrTransportCallbacks, SignalrConnectionsList, SignalrContractDeserializer,
+    my_signal_r_actions::MySignalrActions, MySignalrActionCallbacks, MySignalrAuthenticator,
+    MySignalrMiddleware, MySignalrMiddlewareSettings, MySignalrStreamCallbacks,
+    MySignalrTransportCallbacks, MySignalrUploadCallbacks, OutboundBatchingSettings,
+    OutboundOverflowPolicy, OutboundQueueSettings, SignalrConnectionsList,
+    SignalrContractDeserializer, SignalrTransferFormat, SignalrTransport,
+    StatefulReconnectSettings,
 };
 
-pub struct MiddlewareBuilder<TCtx: Send + Sync + Default + 'static> {
+pub struct MiddlewareBuilder<TCtx: Send + Sync + 'static> {
     hub_name: String,
     signal_r_list: Arc<SignalrConnectionsList<TCtx>>,
     actions: MySignalrActions<TCtx>,
     logger: Arc<dyn Logger + Send + Sync + 'static>,
-    disconnect_timeout: std::time::Duration,
+    settings: MySignalrMiddlewareSettings<TCtx>,
 }
 
 impl<TCtx: Send + Sync + Default + 'static> MiddlewareBuilder<TCtx> {
@@ -20,18 +25,120 @@ impl<TCtx: Send + Sync + Default + 'static> MiddlewareBuilder<TCtx> {
         hub_name: String,
         signalr_list: Arc<SignalrConnectionsList<TCtx>>,
         logger: Arc<dyn Logger + Send + Sync + 'static>,
+    ) -> Self {
+        Self::new_with_ctx_factory(hub_name, signalr_list, logger, |_| Ok(TCtx::default()))
+    }
+}
+
+impl<TCtx: Send + Sync + 'static> MiddlewareBuilder<TCtx> {
+    pub fn new_with_ctx_factory(
+        hub_name: String,
+        signalr_list: Arc<SignalrConnectionsList<TCtx>>,
+        logger: Arc<dyn Logger + Send + Sync + 'static>,
+        ctx_factory: impl Fn(&HttpContext) -> Result<TCtx, HttpFailResult> + Send + Sync + 'static,
     ) -> Self {
         Self {
             hub_name,
             signal_r_list: signalr_list,
             actions: MySignalrActions::new(),
             logger,
-            disconnect_timeout: std::time::Duration::from_secs(60),
+            settings: MySignalrMiddlewareSettings {
+                disconnect_timeout: std::time::Duration::from_secs(60),
+                stateful_reconnect: None,
+                outbound_queue: None,
+                keep_alive_interval: std::time::Duration::from_secs(15),
+                transports: SignalrTransport::ALL.to_vec(),
+                transfer_formats: vec![SignalrTransferFormat::Text, SignalrTransferFormat::Binary],
+                allow_skip_negotiation: false,
+                authenticator: None,
+                ctx_factory: Arc::new(ctx_factory),
+            },
         }
     }
 
     pub fn set_disconnect_timeout(mut self, disconnect_timeout: std::time::Duration) -> Self {
-        self.disconnect_timeout = disconnect_timeout;
+        self.settings.disconnect_timeout = disconnect_timeout;
+        self
+    }
+
+    pub fn with_keep_alive_interval(mut self, keep_alive_interval: std::time::Duration) -> Self {
+        if keep_alive_interval.is_zero() {
+            panic!("Keep alive interval must be greater than zero");
+        }
+
+        self.settings.keep_alive_interval = keep_alive_interval;
+        self
+    }
+
+    pub fn with_transports(mut self, transports: Vec<SignalrTransport>) -> Self {
+        self.settings.transports = transports;
+        self
+    }
+
+    pub fn with_transfer_formats(mut self, transfer_formats: Vec<SignalrTransferFormat>) -> Self {
+        self.settings.transfer_formats = transfer_formats;
+        self
+    }
+
+    pub fn with_allow_skip_negotiation(mut self, allow_skip_negotiation: bool) -> Self {
+        self.settings.allow_skip_negotiation = allow_skip_negotiation;
+        self
+    }
+
+    pub fn with_stateful_reconnect(
+        mut self,
+        buffer_size: usize,
+        grace_period: std::time::Duration,
+    ) -> Self {
+        self.settings.stateful_reconnect = Some(StatefulReconnectSettings {
+            buffer_size,
+            grace_period,
+        });
+        self
+    }
+
+    pub fn with_outbound_queue(
+        mut self,
+        capacity: usize,
+        overflow_policy: OutboundOverflowPolicy,
+    ) -> Self {
+        self.settings.outbound_queue = Some(OutboundQueueSettings {
+            capacity,
+            overflow_policy,
+            batching: self
+                .settings
+                .outbound_queue
+                .and_then(|outbound_queue| outbound_queue.batching),
+        });
+        self
+    }
+
+    pub fn with_batching(mut self, max_delay: std::time::Duration, max_batch_size: usize) -> Self {
+        let mut outbound_queue = match self.settings.outbound_queue {
+            Some(outbound_queue) => outbound_queue,
+            None => panic!(
+                "Outbound queue is not configured. Please use with_outbound_queue before with_batching"
+            ),
+        };
+
+        outbound_queue.batching = Some(OutboundBatchingSettings {
+            max_delay,
+            max_batch_size,
+        });
+
+        self.settings.outbound_queue = Some(outbound_queue);
+        self
+    }
+
+    pub fn with_authenticator(
+        mut self,
+        authenticator: Arc<dyn MySignalrAuthenticator + Send + Sync + 'static>,
+    ) -> Self {
+        if self.settings.authenticator.is_some() {
+            panic!("Authenticator is already registered");
+        }
+
+        self.settings.authenticator = Some(authenticator);
         self
     }
 
@@ -62,12 +169,39 @@ impl<TCtx: Send + Sync + Default + 'static> MiddlewareBuilder<TCtx> {
         self
     }
 
+    pub fn with_stream_action<
+        TContract: SignalrContractDeserializer<Item = TContract> + Send + Sync + 'static,
+        TMySignalrStreamCallbacks: MySignalrStreamCallbacks<TContract, TCtx = TCtx> + Send + Sync + 'static,
+    >(
+        mut self,
+        action_name: String,
+        action: TMySignalrStreamCallbacks,
+    ) -> Self {
+        self.actions
+            .add_stream_action(action_name, action, self.logger.clone());
+        self
+    }
+
+    pub fn with_upload_action<
+        TContract: SignalrContractDeserializer<Item = TContract> + Send + Sync + 'static,
+        TItem: SignalrContractDeserializer<Item = TItem> + Send + Sync + 'static,
+        TMySignalrUploadCallbacks: MySignalrUploadCallbacks<TContract, TItem, TCtx = TCtx> + Send + Sync + 'static,
+    >(
+        mut self,
+        action_name: String,
+        action: TMySignalrUploadCallbacks,
+    ) -> Self {
+        self.actions
+            .add_upload_action(action_name, action, self.logger.clone());
+        self
+    }
+
     pub fn build(self) -> MySignalrMiddleware<TCtx> {
         MySignalrMiddleware::new(
             self.hub_name.as_str(),
             self.signal_r_list,
             self.actions,
-            self.disconnect_timeout,
+            self.settings,
         )
     }
 }
@@ -0,0 +1,23 @@
+use std::sync::Arc;
+
+use my_http_server::{HttpContext, HttpFailResult};
+
+use crate::{
+    MySignalrAuthenticator, OutboundQueueSettings, SignalrTransferFormat, SignalrTransport,
+    StatefulReconnectSettings,
+};
+
+pub type SignalrCtxFactory<TCtx> =
+    Arc<dyn Fn(&HttpContext) -> Result<TCtx, HttpFailResult> + Send + Sync + 'static>;
+
+pub struct MySignalrMiddlewareSettings<TCtx: Send + Sync + 'static> {
+    pub disconnect_timeout: std::time::Duration,
+    pub stateful_reconnect: Option<StatefulReconnectSettings>,
+    pub outbound_queue: Option<OutboundQueueSettings>,
+    pub keep_alive_interval: std::time::Duration,
+    pub transports: Vec<SignalrTransport>,
+    pub transfer_formats: Vec<SignalrTransferFormat>,
+    pub allow_skip_negotiation: bool,
+    pub authenticator: Option<Arc<dyn MySignalrAuthenticator + Send + Sync + 'static>>,
+    pub ctx_factory: SignalrCtxFactory<TCtx>,
+}
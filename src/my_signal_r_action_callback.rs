@@ -2,7 +2,7 @@ use std::{collections::HashMap, sync::Arc};
 
 use rust_extensions::Logger;
 
-use crate::{MySignalrConnection, MySignalrPayloadCallbacks};
+use crate::{MySignalrConnection, MySignalrPayloadCallbacks, SignalrContractSerializer};
 
 pub trait SignalrContractDeserializer {
     type Item;
@@ -14,30 +14,38 @@ pub trait MySignalrActionCallbacks<
     TContract: SignalrContractDeserializer<Item = TContract> + Send + Sync + 'static,
 >
 {
-    type TCtx: Send + Sync + Default + 'static;
+    type TCtx: Send + Sync + 'static;
+    type TResult: SignalrContractSerializer + Send + Sync + 'static;
     async fn on(
         &self,
         connection: &Arc<MySignalrConnection<Self::TCtx>>,
         headers: Option<HashMap<String, String>>,
         data: TContract,
         #[cfg(feature = "my-telemetry")] ctx: &mut crate::SignalRTelemetry,
-    );
+    ) -> Result<Self::TResult, String>;
 }
 
 pub struct MySignalrCallbacksInstance<
     TContract: SignalrContractDeserializer<Item = TContract> + Send + Sync + 'static,
-    TCtx: Send + Sync + Default + 'static,
+    TCtx: Send + Sync + 'static,
+    TResult: SignalrContractSerializer + Send + Sync + 'static,
 > {
     pub action_name: String,
-    pub callback: Arc<dyn MySignalrActionCallbacks<TContract, TCtx = TCtx> + Send + Sync + 'static>,
+    pub callback: Arc<
+        dyn MySignalrActionCallbacks<TContract, TCtx = TCtx, TResult = TResult>
+            + Send
+            + Sync
+            + 'static,
+    >,
     pub logger: Arc<dyn Logger + Send + Sync + 'static>,
 }
 
 #[async_trait::async_trait]
 impl<
         TContract: SignalrContractDeserializer<Item = TContract> + Send + Sync + 'static,
-        TCtx: Send + Sync + Default + 'static,
-    > MySignalrPayloadCallbacks for MySignalrCallbacksInstance<TContract, TCtx>
+        TCtx: Send + Sync + 'static,
+        TResult: SignalrContractSerializer + Send + Sync + 'static,
+    > MySignalrPayloadCallbacks for MySignalrCallbacksInstance<TContract, TCtx, TResult>
 {
     type TCtx = TCtx;
 
@@ -48,39 +56,35 @@ impl<
         action_name: &str,
         data: &[u8],
         #[cfg(feature = "my-telemetry")] ctx: &mut crate::SignalRTelemetry,
-    ) {
-        let mut params = Vec::new();
-        for item in my_json::json_reader::array_parser::JsonArrayIterator::new(data) {
-            match item {
-                Ok(itm) => params.push(itm),
-                Err(err) => {
-                    let mut ctx = HashMap::new();
-                    ctx.insert("action".to_string(), action_name.to_string());
-                    ctx.insert(
-                        "payload".to_string(),
-                        String::from_utf8_lossy(data).to_string(),
-                    );
-                    self.logger.write_fatal_error(
-                        "Signalr payload handler".to_string(),
-                        format!("Can read parameters payloads. Err: {:?}", err),
-                        Some(ctx),
-                    )
-                }
-            }
-        }
+    ) -> Result<Vec<Vec<u8>>, String> {
+        let contract: TContract = deserialize_contract(&self.logger, action_name, data)?;
 
-        match TContract::deserialize(&params) {
-            Ok(contract) => {
-                self.callback
-                    .on(
-                        connection,
-                        headers,
-                        contract,
-                        #[cfg(feature = "my-telemetry")]
-                        ctx,
-                    )
-                    .await;
-            }
+        let result = self
+            .callback
+            .on(
+                connection,
+                headers,
+                contract,
+                #[cfg(feature = "my-telemetry")]
+                ctx,
+            )
+            .await?;
+
+        Ok(result.serialize())
+    }
+}
+
+pub(crate) fn deserialize_contract<
+    TContract: SignalrContractDeserializer<Item = TContract> + Send + Sync + 'static,
+>(
+    logger: &Arc<dyn Logger + Send + Sync + 'static>,
+    action_name: &str,
+    data: &[u8],
+) -> Result<TContract, String> {
+    let mut params = Vec::new();
+    for item in my_json::json_reader::array_parser::JsonArrayIterator::new(data) {
+        match item {
+            Ok(itm) => params.push(itm),
             Err(err) => {
                 let mut ctx = HashMap::new();
                 ctx.insert("action".to_string(), action_name.to_string());
@@ -88,12 +92,36 @@ impl<
                     "payload".to_string(),
                     String::from_utf8_lossy(data).to_string(),
                 );
-                self.logger.write_fatal_error(
+                logger.write_fatal_error(
                     "Signalr payload handler".to_string(),
-                    format!("Can not deserialize payload. Err: {}", err),
+                    format!("Can read parameters payloads. Err: {:?}", err),
                     Some(ctx),
-                )
+                );
+
+                return Err(format!("Can not read parameters of '{}'", action_name));
             }
         }
     }
+
+    match TContract::deserialize(&params) {
+        Ok(contract) => Ok(contract),
+        Err(err) => {
+            let mut ctx = HashMap::new();
+            ctx.insert("action".to_string(), action_name.to_string());
+            ctx.insert(
+                "payload".to_string(),
+                String::from_utf8_lossy(data).to_string(),
+            );
+            logger.write_fatal_error(
+                "Signalr payload handler".to_string(),
+                format!("Can not deserialize payload. Err: {}", err),
+                Some(ctx),
+            );
+
+            Err(format!(
+                "Can not deserialize parameters of '{}'",
+                action_name
+            ))
+        }
+    }
 }
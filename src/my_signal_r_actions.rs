@@ -1,24 +1,37 @@
 use std::{collections::HashMap, sync::Arc};
 
+use futures::{channel::mpsc::UnboundedReceiver, stream::BoxStream};
 use my_http_server::HttpFailResult;
 use rust_extensions::Logger;
 
 use crate::{
     MySignalrActionCallbacks, MySignalrCallbacks, MySignalrCallbacksInstance, MySignalrConnection,
-    MySignalrPayloadCallbacks, MySignalrTransportCallbacks, SignalrContractDeserializer,
+    MySignalrPayloadCallbacks, MySignalrStreamCallbacks, MySignalrStreamCallbacksInstance,
+    MySignalrStreamPayloadCallbacks, MySignalrTransportCallbacks, MySignalrUploadCallbacks,
+    MySignalrUploadCallbacksInstance, MySignalrUploadPayloadCallbacks, SignalrContractDeserializer,
 };
 
-pub struct MySignalrActions<TCtx: Send + Sync + Default + 'static> {
+pub struct MySignalrActions<TCtx: Send + Sync + 'static> {
     pub transport_callbacks:
         Option<Arc<dyn MySignalrTransportCallbacks<TCtx = TCtx> + Send + Sync + 'static>>,
     actions:
         HashMap<String, Arc<dyn MySignalrPayloadCallbacks<TCtx = TCtx> + Send + Sync + 'static>>,
+    stream_actions: HashMap<
+        String,
+        Arc<dyn MySignalrStreamPayloadCallbacks<TCtx = TCtx> + Send + Sync + 'static>,
+    >,
+    upload_actions: HashMap<
+        String,
+        Arc<dyn MySignalrUploadPayloadCallbacks<TCtx = TCtx> + Send + Sync + 'static>,
+    >,
 }
 
-impl<TCtx: Send + Sync + Default + 'static> MySignalrActions<TCtx> {
+impl<TCtx: Send + Sync + 'static> MySignalrActions<TCtx> {
     pub fn new() -> Self {
         Self {
             actions: HashMap::new(),
+            stream_actions: HashMap::new(),
+            upload_actions: HashMap::new(),
             transport_callbacks: None,
         }
     }
@@ -32,9 +45,7 @@ impl<TCtx: Send + Sync + Default + 'static> MySignalrActions<TCtx> {
         callback: TMySignalrPayloadCallbacks,
         logger: Arc<dyn Logger + Send + Sync + 'static>,
     ) {
-        if self.actions.contains_key(&action) {
-            panic!("Signalr action already registered: {}", action);
-        }
+        self.check_action_is_not_registered(&action);
 
         let instance = MySignalrCallbacksInstance {
             action_name: action.to_string(),
@@ -44,10 +55,60 @@ impl<TCtx: Send + Sync + Default + 'static> MySignalrActions<TCtx> {
 
         self.actions.insert(action, Arc::new(instance));
     }
+
+    pub fn add_stream_action<
+        TContract: SignalrContractDeserializer<Item = TContract> + Send + Sync + 'static,
+        TMySignalrStreamCallbacks: MySignalrStreamCallbacks<TContract, TCtx = TCtx> + Send + Sync + 'static,
+    >(
+        &mut self,
+        action: String,
+        callback: TMySignalrStreamCallbacks,
+        logger: Arc<dyn Logger + Send + Sync + 'static>,
+    ) {
+        self.check_action_is_not_registered(&action);
+
+        let instance = MySignalrStreamCallbacksInstance {
+            action_name: action.to_string(),
+            callback: Arc::new(callback),
+            logger,
+        };
+
+        self.stream_actions.insert(action, Arc::new(instance));
+    }
+
+    pub fn add_upload_action<
+        TContract: SignalrContractDeserializer<Item = TContract> + Send + Sync + 'static,
+        TItem: SignalrContractDeserializer<Item = TItem> + Send + Sync + 'static,
+        TMySignalrUploadCallbacks: MySignalrUploadCallbacks<TContract, TItem, TCtx = TCtx> + Send + Sync + 'static,
+    >(
+        &mut self,
+        action: String,
+        callback: TMySignalrUploadCallbacks,
+        logger: Arc<dyn Logger + Send + Sync + 'static>,
+    ) {
+        self.check_action_is_not_registered(&action);
+
+        let instance = MySignalrUploadCallbacksInstance {
+            action_name: action.to_string(),
+            callback: Arc::new(callback),
+            logger,
+        };
+
+        self.upload_actions.insert(action, Arc::new(instance));
+    }
+
+    fn check_action_is_not_registered(&self, action: &str) {
+        if self.actions.contains_key(action)
+            || self.stream_actions.contains_key(action)
+            || self.upload_actions.contains_key(action)
+        {
+            panic!("Signalr action already registered: {}", action);
+        }
+    }
 }
 
 #[async_trait::async_trait]
-impl<TCtx: Send + Sync + Default + 'static> MySignalrCallbacks for MySignalrActions<TCtx> {
+impl<TCtx: Send + Sync + 'static> MySignalrCallbacks for MySignalrActions<TCtx> {
     type TCtx = TCtx;
 
     async fn connected(
@@ -71,6 +132,26 @@ impl<TCtx: Send + Sync + Default + 'static> MySignalrCallbacks for MySignalrActi
             c.on_ping(connection).await
         }
     }
+
+    async fn user_connected(
+        &self,
+        user_id: &str,
+        connection: &Arc<MySignalrConnection<Self::TCtx>>,
+    ) {
+        if let Some(c) = self.transport_callbacks.as_ref() {
+            c.user_connected(user_id, connection).await
+        }
+    }
+
+    async fn user_disconnected(
+        &self,
+        user_id: &str,
+        connection: &Arc<MySignalrConnection<Self::TCtx>>,
+    ) {
+        if let Some(c) = self.transport_callbacks.as_ref() {
+            c.user_disconnected(user_id, connection).await
+        }
+    }
     async fn on(
         &self,
         signalr_connection: Arc<MySignalrConnection<Self::TCtx>>,
@@ -78,18 +159,73 @@ impl<TCtx: Send + Sync + Default + 'static> MySignalrCallbacks for MySignalrActi
         action_name: String,
         data: Vec<u8>,
         #[cfg(feature = "my-telemetry")] ctx: &mut crate::SignalRTelemetry,
-    ) {
-        if let Some(action) = self.actions.get(action_name.as_str()) {
-            action
-                .on(
-                    &signalr_connection,
-                    headers,
-                    &action_name,
-                    &data,
-                    #[cfg(feature = "my-telemetry")]
-                    ctx,
-                )
-                .await;
+    ) -> Result<Vec<Vec<u8>>, String> {
+        match self.actions.get(action_name.as_str()) {
+            Some(action) => {
+                action
+                    .on(
+                        &signalr_connection,
+                        headers,
+                        &action_name,
+                        &data,
+                        #[cfg(feature = "my-telemetry")]
+                        ctx,
+                    )
+                    .await
+            }
+            None => Err(format!("Unknown hub method '{}'", action_name)),
+        }
+    }
+
+    async fn on_stream(
+        &self,
+        signalr_connection: Arc<MySignalrConnection<Self::TCtx>>,
+        headers: Option<HashMap<String, String>>,
+        action_name: String,
+        data: Vec<u8>,
+        #[cfg(feature = "my-telemetry")] ctx: &mut crate::SignalRTelemetry,
+    ) -> Result<BoxStream<'static, Vec<Vec<u8>>>, String> {
+        match self.stream_actions.get(action_name.as_str()) {
+            Some(action) => {
+                action
+                    .on(
+                        &signalr_connection,
+                        headers,
+                        &action_name,
+                        &data,
+                        #[cfg(feature = "my-telemetry")]
+                        ctx,
+                    )
+                    .await
+            }
+            None => Err(format!("Unknown stream hub method '{}'", action_name)),
+        }
+    }
+
+    async fn on_upload(
+        &self,
+        signalr_connection: Arc<MySignalrConnection<Self::TCtx>>,
+        headers: Option<HashMap<String, String>>,
+        action_name: String,
+        data: Vec<u8>,
+        streams: Vec<UnboundedReceiver<Vec<u8>>>,
+        #[cfg(feature = "my-telemetry")] ctx: &mut crate::SignalRTelemetry,
+    ) -> Result<Vec<Vec<u8>>, String> {
+        match self.upload_actions.get(action_name.as_str()) {
+            Some(action) => {
+                action
+                    .on(
+                        &signalr_connection,
+                        headers,
+                        &action_name,
+                        &data,
+                        streams,
+                        #[cfg(feature = "my-telemetry")]
+                        ctx,
+                    )
+                    .await
+            }
+            None => Err(format!("Unknown upload hub method '{}'", action_name)),
         }
     }
 }
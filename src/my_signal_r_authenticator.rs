@@ -0,0 +1,8 @@
+use my_http_server::{HttpContext, HttpFailResult};
+
+use crate::SignalrIdentity;
+
+#[async_trait::async_trait]
+pub trait MySignalrAuthenticator {
+    async fn authenticate(&self, ctx: &HttpContext) -> Result<SignalrIdentity, HttpFailResult>;
+}
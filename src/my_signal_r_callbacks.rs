@@ -1,18 +1,31 @@
 use std::{collections::HashMap, sync::Arc};
 
+use futures::{channel::mpsc::UnboundedReceiver, stream::BoxStream};
 use my_http_server::HttpFailResult;
 
 use crate::MySignalrConnection;
 
 #[async_trait::async_trait]
 pub trait MySignalrCallbacks {
-    type TCtx: Send + Sync + Default + 'static;
+    type TCtx: Send + Sync + 'static;
     async fn connected(
         &self,
         connection: &Arc<MySignalrConnection<Self::TCtx>>,
     ) -> Result<(), HttpFailResult>;
     async fn disconnected(&self, connection: &Arc<MySignalrConnection<Self::TCtx>>);
     async fn on_ping(&self, connection: &Arc<MySignalrConnection<Self::TCtx>>);
+    async fn user_connected(
+        &self,
+        _user_id: &str,
+        _connection: &Arc<MySignalrConnection<Self::TCtx>>,
+    ) {
+    }
+    async fn user_disconnected(
+        &self,
+        _user_id: &str,
+        _connection: &Arc<MySignalrConnection<Self::TCtx>>,
+    ) {
+    }
     async fn on(
         &self,
         connection: Arc<MySignalrConnection<Self::TCtx>>,
@@ -20,29 +33,85 @@ pub trait MySignalrCallbacks {
         action_name: String,
         data: Vec<u8>,
         #[cfg(feature = "my-telemetry")] ctx: &mut crate::SignalRTelemetry,
-    );
+    ) -> Result<Vec<Vec<u8>>, String>;
+    async fn on_stream(
+        &self,
+        connection: Arc<MySignalrConnection<Self::TCtx>>,
+        headers: Option<HashMap<String, String>>,
+        action_name: String,
+        data: Vec<u8>,
+        #[cfg(feature = "my-telemetry")] ctx: &mut crate::SignalRTelemetry,
+    ) -> Result<BoxStream<'static, Vec<Vec<u8>>>, String>;
+    async fn on_upload(
+        &self,
+        connection: Arc<MySignalrConnection<Self::TCtx>>,
+        headers: Option<HashMap<String, String>>,
+        action_name: String,
+        data: Vec<u8>,
+        streams: Vec<UnboundedReceiver<Vec<u8>>>,
+        #[cfg(feature = "my-telemetry")] ctx: &mut crate::SignalRTelemetry,
+    ) -> Result<Vec<Vec<u8>>, String>;
 }
 
 #[async_trait::async_trait]
 pub trait MySignalrTransportCallbacks {
-    type TCtx: Send + Sync + Default + 'static;
+    type TCtx: Send + Sync + 'static;
     async fn connected(
         &self,
         connection: &Arc<MySignalrConnection<Self::TCtx>>,
     ) -> Result<(), HttpFailResult>;
     async fn disconnected(&self, connection: &Arc<MySignalrConnection<Self::TCtx>>);
     async fn on_ping(&self, connection: &Arc<MySignalrConnection<Self::TCtx>>);
+    async fn user_connected(
+        &self,
+        _user_id: &str,
+        _connection: &Arc<MySignalrConnection<Self::TCtx>>,
+    ) {
+    }
+    async fn user_disconnected(
+        &self,
+        _user_id: &str,
+        _connection: &Arc<MySignalrConnection<Self::TCtx>>,
+    ) {
+    }
 }
 
 #[async_trait::async_trait]
 pub trait MySignalrPayloadCallbacks {
-    type TCtx: Send + Sync + Default + 'static;
+    type TCtx: Send + Sync + 'static;
+    async fn on(
+        &self,
+        signalr_connection: &Arc<MySignalrConnection<Self::TCtx>>,
+        headers: Option<HashMap<String, String>>,
+        action_name: &str,
+        data: &[u8],
+        #[cfg(feature = "my-telemetry")] ctx: &mut crate::SignalRTelemetry,
+    ) -> Result<Vec<Vec<u8>>, String>;
+}
+
+#[async_trait::async_trait]
+pub trait MySignalrStreamPayloadCallbacks {
+    type TCtx: Send + Sync + 'static;
+    async fn on(
+        &self,
+        signalr_connection: &Arc<MySignalrConnection<Self::TCtx>>,
+        headers: Option<HashMap<String, String>>,
+        action_name: &str,
+        data: &[u8],
+        #[cfg(feature = "my-telemetry")] ctx: &mut crate::SignalRTelemetry,
+    ) -> Result<BoxStream<'static, Vec<Vec<u8>>>, String>;
+}
+
+#[async_trait::async_trait]
+pub trait MySignalrUploadPayloadCallbacks {
+    type TCtx: Send + Sync + 'static;
     async fn on(
         &self,
         signalr_connection: &Arc<MySignalrConnection<Self::TCtx>>,
         headers: Option<HashMap<String, String>>,
         action_name: &str,
         data: &[u8],
+        streams: Vec<UnboundedReceiver<Vec<u8>>>,
         #[cfg(feature = "my-telemetry")] ctx: &mut crate::SignalRTelemetry,
-    );
+    ) -> Result<Vec<Vec<u8>>, String>;
 }
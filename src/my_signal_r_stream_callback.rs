@@ -0,0 +1,71 @@
+use std::{collections::HashMap, sync::Arc};
+
+use futures::{stream::BoxStream, StreamExt};
+use rust_extensions::Logger;
+
+use crate::{
+    my_signal_r_action_callback::deserialize_contract, MySignalrConnection,
+    MySignalrStreamPayloadCallbacks, SignalrContractDeserializer, SignalrContractSerializer,
+};
+
+#[async_trait::async_trait]
+pub trait MySignalrStreamCallbacks<
+    TContract: SignalrContractDeserializer<Item = TContract> + Send + Sync + 'static,
+>
+{
+    type TCtx: Send + Sync + 'static;
+    type TItem: SignalrContractSerializer + Send + Sync + 'static;
+    async fn on(
+        &self,
+        connection: &Arc<MySignalrConnection<Self::TCtx>>,
+        headers: Option<HashMap<String, String>>,
+        data: TContract,
+        #[cfg(feature = "my-telemetry")] ctx: &mut crate::SignalRTelemetry,
+    ) -> Result<BoxStream<'static, Self::TItem>, String>;
+}
+
+pub struct MySignalrStreamCallbacksInstance<
+    TContract: SignalrContractDeserializer<Item = TContract> + Send + Sync + 'static,
+    TCtx: Send + Sync + 'static,
+    TItem: SignalrContractSerializer + Send + Sync + 'static,
+> {
+    pub action_name: String,
+    pub callback: Arc<
+        dyn MySignalrStreamCallbacks<TContract, TCtx = TCtx, TItem = TItem> + Send + Sync + 'static,
+    >,
+    pub logger: Arc<dyn Logger + Send + Sync + 'static>,
+}
+
+#[async_trait::async_trait]
+impl<
+        TContract: SignalrContractDeserializer<Item = TContract> + Send + Sync + 'static,
+        TCtx: Send + Sync + 'static,
+        TItem: SignalrContractSerializer + Send + Sync + 'static,
+    > MySignalrStreamPayloadCallbacks for MySignalrStreamCallbacksInstance<TContract, TCtx, TItem>
+{
+    type TCtx = TCtx;
+
+    async fn on(
+        &self,
+        connection: &Arc<MySignalrConnection<Self::TCtx>>,
+        headers: Option<HashMap<String, String>>,
+        action_name: &str,
+        data: &[u8],
+        #[cfg(feature = "my-telemetry")] ctx: &mut crate::SignalRTelemetry,
+    ) -> Result<BoxStream<'static, Vec<Vec<u8>>>, String> {
+        let contract: TContract = deserialize_contract(&self.logger, action_name, data)?;
+
+        let stream = self
+            .callback
+            .on(
+                connection,
+                headers,
+                contract,
+                #[cfg(feature = "my-telemetry")]
+                ctx,
+            )
+            .await?;
+
+        Ok(stream.map(|item| item.serialize()).boxed())
+    }
+}
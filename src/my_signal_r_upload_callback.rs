@@ -0,0 +1,112 @@
+use std::{collections::HashMap, sync::Arc};
+
+use futures::{channel::mpsc::UnboundedReceiver, stream::BoxStream, StreamExt};
+use rust_extensions::Logger;
+
+use crate::{
+    my_signal_r_action_callback::deserialize_contract, MySignalrConnection,
+    MySignalrUploadPayloadCallbacks, SignalrContractDeserializer, SignalrContractSerializer,
+};
+
+#[async_trait::async_trait]
+pub trait MySignalrUploadCallbacks<
+    TContract: SignalrContractDeserializer<Item = TContract> + Send + Sync + 'static,
+    TItem: SignalrContractDeserializer<Item = TItem> + Send + Sync + 'static,
+>
+{
+    type TCtx: Send + Sync + 'static;
+    type TResult: SignalrContractSerializer + Send + Sync + 'static;
+    async fn on(
+        &self,
+        connection: &Arc<MySignalrConnection<Self::TCtx>>,
+        headers: Option<HashMap<String, String>>,
+        data: TContract,
+        streams: Vec<BoxStream<'static, TItem>>,
+        #[cfg(feature = "my-telemetry")] ctx: &mut crate::SignalRTelemetry,
+    ) -> Result<Self::TResult, String>;
+}
+
+pub struct MySignalrUploadCallbacksInstance<
+    TContract: SignalrContractDeserializer<Item = TContract> + Send + Sync + 'static,
+    TItem: SignalrContractDeserializer<Item = TItem> + Send + Sync + 'static,
+    TCtx: Send + Sync + 'static,
+    TResult: SignalrContractSerializer + Send + Sync + 'static,
+> {
+    pub action_name: String,
+    pub callback: Arc<
+        dyn MySignalrUploadCallbacks<TContract, TItem, TCtx = TCtx, TResult = TResult>
+            + Send
+            + Sync
+            + 'static,
+    >,
+    pub logger: Arc<dyn Logger + Send + Sync + 'static>,
+}
+
+#[async_trait::async_trait]
+impl<
+        TContract: SignalrContractDeserializer<Item = TContract> + Send + Sync + 'static,
+        TItem: SignalrContractDeserializer<Item = TItem> + Send + Sync + 'static,
+        TCtx: Send + Sync + 'static,
+        TResult: SignalrContractSerializer + Send + Sync + 'static,
+    > MySignalrUploadPayloadCallbacks
+    for MySignalrUploadCallbacksInstance<TContract, TItem, TCtx, TResult>
+{
+    type TCtx = TCtx;
+
+    async fn on(
+        &self,
+        connection: &Arc<MySignalrConnection<Self::TCtx>>,
+        headers: Option<HashMap<String, String>>,
+        action_name: &str,
+        data: &[u8],
+        streams: Vec<UnboundedReceiver<Vec<u8>>>,
+        #[cfg(feature = "my-telemetry")] ctx: &mut crate::SignalRTelemetry,
+    ) -> Result<Vec<Vec<u8>>, String> {
+        let contract: TContract = deserialize_contract(&self.logger, action_name, data)?;
+
+        let streams = streams
+            .into_iter()
+            .map(|stream| {
+                let logger = self.logger.clone();
+                let action_name = action_name.to_string();
+                stream
+                    .filter_map(move |item| {
+                        let result = match TItem::deserialize(&[item.as_slice()]) {
+                            Ok(item) => Some(item),
+                            Err(err) => {
+                                let mut ctx = HashMap::new();
+                                ctx.insert("action".to_string(), action_name.to_string());
+                                ctx.insert(
+                                    "payload".to_string(),
+                                    String::from_utf8_lossy(&item).to_string(),
+                                );
+                                logger.write_fatal_error(
+                                    "Signalr upload stream handler".to_string(),
+                                    format!("Can not deserialize stream item. Err: {}", err),
+                                    Some(ctx),
+                                );
+                                None
+                            }
+                        };
+
+                        futures::future::ready(result)
+                    })
+                    .boxed()
+            })
+            .collect();
+
+        let result = self
+            .callback
+            .on(
+                connection,
+                headers,
+                contract,
+                streams,
+                #[cfg(feature = "my-telemetry")]
+                ctx,
+            )
+            .await?;
+
+        Ok(result.serialize())
+    }
+}
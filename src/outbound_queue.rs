@@ -0,0 +1,267 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    time::Duration,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutboundOverflowPolicy {
+    DropOldest,
+    DropNewest,
+    Disconnect,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct OutboundQueueSettings {
+    pub capacity: usize,
+    pub overflow_policy: OutboundOverflowPolicy,
+    pub batching: Option<OutboundBatchingSettings>,
+}
+
+impl OutboundQueueSettings {
+    pub fn unbounded() -> Self {
+        Self {
+            capacity: usize::MAX,
+            overflow_policy: OutboundOverflowPolicy::DropNewest,
+            batching: None,
+        }
+    }
+
+    pub fn get_max_batch_size(&self) -> usize {
+        match self.batching {
+            Some(batching) => batching.max_batch_size.max(1),
+            None => 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct OutboundBatchingSettings {
+    pub max_delay: Duration,
+    pub max_batch_size: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutboundQueuePushResult {
+    Queued,
+    Replaced,
+    Dropped,
+    Overflowed,
+}
+
+pub struct OutboundQueue {
+    settings: OutboundQueueSettings,
+    items: VecDeque<(Option<String>, Vec<u8>)>,
+    first_position: u64,
+    conflated_positions: HashMap<String, u64>,
+}
+
+impl OutboundQueue {
+    pub fn new(settings: OutboundQueueSettings) -> Self {
+        Self {
+            settings,
+            items: VecDeque::new(),
+            first_position: 0,
+            conflated_positions: HashMap::new(),
+        }
+    }
+
+    pub fn push(
+        &mut self,
+        conflation_key: Option<&str>,
+        payload: Vec<u8>,
+    ) -> OutboundQueuePushResult {
+        if let Some(conflation_key) = conflation_key {
+            if let Some(position) = self.conflated_positions.get(conflation_key) {
+                let index = (*position - self.first_position) as usize;
+                self.items[index].1 = payload;
+                return OutboundQueuePushResult::Replaced;
+            }
+        }
+
+        if self.items.len() < self.settings.capacity {
+            self.push_back(conflation_key, payload);
+            return OutboundQueuePushResult::Queued;
+        }
+
+        match self.settings.overflow_policy {
+            OutboundOverflowPolicy::DropOldest => {
+                self.pop();
+                self.push_back(conflation_key, payload);
+                OutboundQueuePushResult::Dropped
+            }
+            OutboundOverflowPolicy::DropNewest => OutboundQueuePushResult::Dropped,
+            OutboundOverflowPolicy::Disconnect => {
+                self.clear();
+                OutboundQueuePushResult::Overflowed
+            }
+        }
+    }
+
+    fn push_back(&mut self, conflation_key: Option<&str>, payload: Vec<u8>) {
+        if let Some(conflation_key) = conflation_key {
+            let position = self.first_position + self.items.len() as u64;
+            self.conflated_positions
+                .insert(conflation_key.to_string(), position);
+        }
+
+        self.items
+            .push_back((conflation_key.map(|key| key.to_string()), payload));
+    }
+
+    pub fn pop(&mut self) -> Option<Vec<u8>> {
+        let (conflation_key, payload) = self.items.pop_front()?;
+        self.first_position += 1;
+
+        if let Some(conflation_key) = conflation_key {
+            self.conflated_positions.remove(&conflation_key);
+        }
+
+        Some(payload)
+    }
+
+    pub fn pop_batch(&mut self, max_batch_size: usize) -> Option<Vec<u8>> {
+        let mut result = self.pop()?;
+
+        for _ in 1..max_batch_size {
+            match self.pop() {
+                Some(payload) => result.extend_from_slice(payload.as_slice()),
+                None => break,
+            }
+        }
+
+        Some(result)
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.conflated_positions.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_queue(overflow_policy: OutboundOverflowPolicy) -> OutboundQueue {
+        let mut queue = OutboundQueue::new(OutboundQueueSettings {
+            capacity: 2,
+            overflow_policy,
+            batching: None,
+        });
+
+        assert_eq!(OutboundQueuePushResult::Queued, queue.push(None, vec![1]));
+        assert_eq!(OutboundQueuePushResult::Queued, queue.push(None, vec![2]));
+
+        queue
+    }
+
+    #[test]
+    fn test_drop_oldest() {
+        let mut queue = create_queue(OutboundOverflowPolicy::DropOldest);
+
+        assert_eq!(OutboundQueuePushResult::Dropped, queue.push(None, vec![3]));
+        assert_eq!(2, queue.len());
+        assert_eq!(Some(vec![2]), queue.pop());
+        assert_eq!(Some(vec![3]), queue.pop());
+        assert_eq!(None, queue.pop());
+    }
+
+    #[test]
+    fn test_drop_newest() {
+        let mut queue = create_queue(OutboundOverflowPolicy::DropNewest);
+
+        assert_eq!(OutboundQueuePushResult::Dropped, queue.push(None, vec![3]));
+        assert_eq!(Some(vec![1]), queue.pop());
+        assert_eq!(Some(vec![2]), queue.pop());
+        assert_eq!(None, queue.pop());
+    }
+
+    #[test]
+    fn test_disconnect() {
+        let mut queue = create_queue(OutboundOverflowPolicy::Disconnect);
+
+        assert_eq!(
+            OutboundQueuePushResult::Overflowed,
+            queue.push(None, vec![3])
+        );
+        assert_eq!(0, queue.len());
+    }
+
+    #[test]
+    fn test_conflation_replaces_queued_message() {
+        let mut queue = OutboundQueue::new(OutboundQueueSettings {
+            capacity: 2,
+            overflow_policy: OutboundOverflowPolicy::Disconnect,
+            batching: None,
+        });
+
+        assert_eq!(
+            OutboundQueuePushResult::Queued,
+            queue.push(Some("BTCUSD"), vec![1])
+        );
+        assert_eq!(
+            OutboundQueuePushResult::Queued,
+            queue.push(Some("ETHUSD"), vec![2])
+        );
+        assert_eq!(
+            OutboundQueuePushResult::Replaced,
+            queue.push(Some("BTCUSD"), vec![3])
+        );
+
+        assert_eq!(Some(vec![3]), queue.pop());
+        assert_eq!(Some(vec![2]), queue.pop());
+
+        assert_eq!(
+            OutboundQueuePushResult::Queued,
+            queue.push(Some("BTCUSD"), vec![4])
+        );
+        assert_eq!(1, queue.len());
+    }
+
+    #[test]
+    fn test_pop_batch() {
+        let mut queue = OutboundQueue::new(OutboundQueueSettings::unbounded());
+
+        queue.push(None, vec![1, 30]);
+        queue.push(None, vec![2, 30]);
+        queue.push(None, vec![3, 30]);
+
+        assert_eq!(Some(vec![1, 30, 2, 30]), queue.pop_batch(2));
+        assert_eq!(Some(vec![3, 30]), queue.pop_batch(2));
+        assert_eq!(None, queue.pop_batch(2));
+    }
+
+    #[test]
+    fn test_conflation_after_drop_oldest() {
+        let mut queue = OutboundQueue::new(OutboundQueueSettings {
+            capacity: 2,
+            overflow_policy: OutboundOverflowPolicy::DropOldest,
+            batching: None,
+        });
+
+        queue.push(Some("BTCUSD"), vec![1]);
+        queue.push(Some("ETHUSD"), vec![2]);
+        queue.push(Some("XRPUSD"), vec![3]);
+
+        assert_eq!(
+            OutboundQueuePushResult::Replaced,
+            queue.push(Some("XRPUSD"), vec![4])
+        );
+        assert_eq!(
+            OutboundQueuePushResult::Dropped,
+            queue.push(Some("BTCUSD"), vec![5])
+        );
+
+        assert_eq!(Some(vec![4]), queue.pop());
+        assert_eq!(Some(vec![5]), queue.pop());
+        assert_eq!(None, queue.pop());
+    }
+}
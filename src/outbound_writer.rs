@@ -0,0 +1,46 @@
+use std::sync::Arc;
+
+use crate::{MySignalrConnection, OutboundBatchingSettings};
+
+pub async fn start<TCtx: Send + Sync + 'static>(connection: Arc<MySignalrConnection<TCtx>>) {
+    #[cfg(feature = "debug_ws")]
+    println!(
+        "Signalr {} with connection token {:?} started outbound writer",
+        connection.connection_id, connection.connection_token
+    );
+
+    let (batching, max_batch_size) = match connection.outbound_queue.as_ref() {
+        Some(outbound_queue) => (outbound_queue.batching, outbound_queue.get_max_batch_size()),
+        None => (None, 1),
+    };
+
+    while connection.is_connected() {
+        if let Some(batching) = batching.as_ref() {
+            if connection.get_outbound_queue_depth() > 0 {
+                wait_for_batch(&connection, batching).await;
+            }
+        }
+
+        if !connection.send_next_outbound_payload(max_batch_size).await {
+            connection.wait_for_outbound_payload().await;
+        }
+    }
+}
+
+async fn wait_for_batch<TCtx: Send + Sync + 'static>(
+    connection: &MySignalrConnection<TCtx>,
+    batching: &OutboundBatchingSettings,
+) {
+    let deadline = tokio::time::Instant::now() + batching.max_delay;
+
+    while connection.is_connected()
+        && connection.get_outbound_queue_depth() < batching.max_batch_size
+    {
+        if tokio::time::timeout_at(deadline, connection.wait_for_outbound_payload())
+            .await
+            .is_err()
+        {
+            break;
+        }
+    }
+}
@@ -1,22 +1,28 @@
 use std::sync::Arc;
 
+use my_http_server::HttpFailResult;
 use my_http_server_web_sockets::MyWebSocket;
 
-use crate::{MySignalrCallbacks, MySignalrConnection, SignalrConnectionsList};
+use crate::{
+    MySignalrCallbacks, MySignalrConnection, SignalrConnectionSettings, SignalrConnectionsList,
+    SignalrIdentity,
+};
 
 pub async fn process_connect<
-    TCtx: Send + Sync + Default + 'static,
-    TMySignalrCallbacks: MySignalrCallbacks<TCtx = TCtx> + Send + Sync + 'static,
+    TCtx: Send + Sync + 'static,
+    TMySignalrCallbacks: MySignalrCallbacks<TCtx = TCtx> + Send + Sync + 'static + ?Sized,
 >(
     connections_callback: &Arc<TMySignalrCallbacks>,
     signal_r_list: &Arc<SignalrConnectionsList<TCtx>>,
-    negotiation_version: usize,
     web_socket: Option<Arc<MyWebSocket>>,
-) -> (Arc<MySignalrConnection<TCtx>>, String) {
+    settings: SignalrConnectionSettings,
+    identity: Option<SignalrIdentity>,
+    ctx: TCtx,
+) -> Result<Arc<MySignalrConnection<TCtx>>, HttpFailResult> {
     let mut connection_id = uuid::Uuid::new_v4().to_string();
     connection_id = connection_id.replace("-", "");
 
-    let conenction_token = if negotiation_version == 0 {
+    let conenction_token = if settings.negotiation_version == 0 {
         None
     } else {
         let mut connection_token = uuid::Uuid::new_v4().to_string();
@@ -24,28 +30,29 @@ pub async fn process_connect<
         Some(connection_token)
     };
 
-    let result = crate::messages::generate_negotiate_response(
-        negotiation_version,
-        connection_id.as_str(),
-        &conenction_token,
-    );
-
     let signal_r_connection = MySignalrConnection::new(
         connection_id,
         conenction_token,
-        negotiation_version,
         web_socket,
+        settings,
+        identity,
+        ctx,
     );
     let signal_r_connection = Arc::new(signal_r_connection);
 
-    connections_callback
-        .connected(&signal_r_connection)
-        .await
-        .unwrap();
+    connections_callback.connected(&signal_r_connection).await?;
 
-    signal_r_list
+    let first_user_connection = signal_r_list
         .add_signalr_connection(signal_r_connection.clone())
         .await;
 
-    (signal_r_connection, result)
+    if first_user_connection {
+        if let Some(user_id) = signal_r_connection.user_id() {
+            connections_callback
+                .user_connected(user_id, &signal_r_connection)
+                .await;
+        }
+    }
+
+    Ok(signal_r_connection)
 }
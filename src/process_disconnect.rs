@@ -2,21 +2,28 @@ use std::sync::Arc;
 
 use crate::{MySignalrCallbacks, MySignalrConnection, SignalrConnectionsList};
 
-pub async fn process_disconnect<TCtx: Send + Sync + Default + 'static>(
-    sockets_list: &Arc<SignalrConnectionsList<TCtx>>,
-    signalr_connection: &Arc<MySignalrConnection<TCtx>>,
+pub async fn process_disconnect<TCtx: Send + Sync + 'static>(
+    sockets_list: &SignalrConnectionsList<TCtx>,
+    connection_token: &str,
     connect_events: &Arc<dyn MySignalrCallbacks<TCtx = TCtx> + Send + Sync + 'static>,
-) {
-    let removed_connection = sockets_list
-        .remove(signalr_connection.get_list_index())
-        .await;
+) -> Option<Arc<MySignalrConnection<TCtx>>> {
+    let (removed_connection, last_user_connection) =
+        sockets_list.remove_connection(connection_token).await?;
 
-    if let Some(removed_connection) = removed_connection {
-        #[cfg(feature = "debug_ws")]
-        println!(
-            "Signalr {} is diconnectd with connection token {:?}",
-            removed_connection.connection_id, removed_connection.connection_token
-        );
-        connect_events.disconnected(&removed_connection).await;
+    #[cfg(feature = "debug_ws")]
+    println!(
+        "Signalr {} is diconnectd with connection token {:?}",
+        removed_connection.connection_id, removed_connection.connection_token
+    );
+    connect_events.disconnected(&removed_connection).await;
+
+    if last_user_connection {
+        if let Some(user_id) = removed_connection.user_id() {
+            connect_events
+                .user_disconnected(user_id, &removed_connection)
+                .await;
+        }
     }
+
+    Some(removed_connection)
 }
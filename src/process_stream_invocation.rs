@@ -0,0 +1,57 @@
+use std::sync::Arc;
+
+use futures::{stream::BoxStream, StreamExt};
+use tokio::sync::oneshot;
+
+use crate::MySignalrConnection;
+
+pub async fn process_stream_invocation<TCtx: Send + Sync + 'static>(
+    signalr_connection: Arc<MySignalrConnection<TCtx>>,
+    invocation_id: String,
+    stream: Result<BoxStream<'static, Vec<Vec<u8>>>, String>,
+    mut cancel: oneshot::Receiver<()>,
+) {
+    let mut stream = match stream {
+        Ok(stream) => stream,
+        Err(err) => {
+            signalr_connection
+                .unregister_stream(invocation_id.as_str())
+                .await;
+            signalr_connection
+                .send_completion(invocation_id.as_str(), &Err(err))
+                .await;
+            return;
+        }
+    };
+
+    loop {
+        tokio::select! {
+            item = stream.next() => {
+                match item {
+                    Some(item) => {
+                        signalr_connection
+                            .send_stream_item(invocation_id.as_str(), &item)
+                            .await;
+                    }
+                    None => {
+                        signalr_connection
+                            .unregister_stream(invocation_id.as_str())
+                            .await;
+                        signalr_connection
+                            .send_completion(invocation_id.as_str(), &Ok(vec![]))
+                            .await;
+                        return;
+                    }
+                }
+            }
+            _ = &mut cancel => {
+                #[cfg(feature = "debug_ws")]
+                println!(
+                    "Signalr {} stream {} is canceled",
+                    signalr_connection.connection_id, invocation_id
+                );
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use std::sync::Arc;
+
+use futures::StreamExt;
+
+use crate::{
+    MySignalrConnection, SignalrConnectionsList, SignalrContractSerializer, SignalrInvocationFrame,
+    TagQuery, DEFAULT_MAX_CONCURRENT_SENDS,
+};
+
+/// Conflation only happens for WebSocket connections with an outbound queue
+/// (`MiddlewareBuilder::with_outbound_queue`). Connections without a queue, on
+/// LongPolling or ServerSentEvents, or with stateful reconnect get every message.
+pub struct SignalrConflatingPublisher<
+    TContract: SignalrContractSerializer + Send + Sync + 'static,
+    TCtx: Send + Sync + 'static,
+> {
+    signalr_list: Arc<SignalrConnectionsList<TCtx>>,
+    itm: std::marker::PhantomData<TContract>,
+    action_name: String,
+    max_concurrent_sends: usize,
+}
+
+impl<TContract: SignalrContractSerializer + Send + Sync + 'static, TCtx: Send + Sync + 'static>
+    SignalrConflatingPublisher<TContract, TCtx>
+{
+    pub fn new(action_name: String, signalr_list: Arc<SignalrConnectionsList<TCtx>>) -> Self {
+        Self {
+            action_name,
+            signalr_list,
+            itm: std::marker::PhantomData,
+            max_concurrent_sends: DEFAULT_MAX_CONCURRENT_SENDS,
+        }
+    }
+
+    pub fn with_max_concurrent_sends(mut self, max_concurrent_sends: usize) -> Self {
+        self.max_concurrent_sends = max_concurrent_sends;
+        self
+    }
+
+    pub async fn broadcast_to_all(&self, conflation_key: &str, contract: TContract) {
+        if let Some(connections) = self.signalr_list.get_all().await {
+            self.publish(connections, conflation_key, contract).await;
+        }
+    }
+
+    pub async fn send_to_connection(
+        &self,
+        connection: &MySignalrConnection<TCtx>,
+        conflation_key: &str,
+        contract: TContract,
+    ) {
+        let frame = SignalrInvocationFrame::from_contract(self.action_name.as_str(), contract);
+        let conflation_key = self.get_conflation_key(conflation_key);
+        connection
+            .send_frame_conflated(&frame, conflation_key.as_str())
+            .await;
+    }
+
+    pub async fn send_to_user(&self, user_id: &str, conflation_key: &str, contract: TContract) {
+        if let Some(connections) = self.signalr_list.get_user_connections(user_id).await {
+            self.publish(connections, conflation_key, contract).await;
+        }
+    }
+
+    pub async fn send_to_group(&self, group: &str, conflation_key: &str, contract: TContract) {
+        if let Some(connections) = self.signalr_list.get_group_connections(group).await {
+            self.publish(connections, conflation_key, contract).await;
+        }
+    }
+
+    pub async fn send_to_tagged_connections(
+        &self,
+        key: &str,
+        conflation_key: &str,
+        contract: TContract,
+    ) {
+        if let Some(connections) = self.signalr_list.get_tagged_connections(key).await {
+            self.publish(connections, conflation_key, contract).await;
+        }
+    }
+
+    pub async fn send_to_tagged_connections_with_value(
+        &self,
+        key: &str,
+        value: &str,
+        conflation_key: &str,
+        contract: TContract,
+    ) {
+        if let Some(connections) = self
+            .signalr_list
+            .get_tagged_connections_with_value(key, value)
+            .await
+        {
+            self.publish(connections, conflation_key, contract).await;
+        }
+    }
+
+    pub async fn send_to_query(&self, query: &TagQuery, conflation_key: &str, contract: TContract) {
+        if let Some(connections) = self.signalr_list.get_connections_by_query(query).await {
+            self.publish(connections, conflation_key, contract).await;
+        }
+    }
+
+    fn get_conflation_key(&self, conflation_key: &str) -> String {
+        format!("{}/{}", self.action_name, conflation_key)
+    }
+
+    async fn publish(
+        &self,
+        connections: Vec<Arc<MySignalrConnection<TCtx>>>,
+        conflation_key: &str,
+        contract: TContract,
+    ) {
+        let frame = SignalrInvocationFrame::from_contract(self.action_name.as_str(), contract);
+        let frame = &frame;
+
+        let conflation_key = self.get_conflation_key(conflation_key);
+        let conflation_key = conflation_key.as_str();
+
+        futures::stream::iter(connections.iter())
+            .for_each_concurrent(self.max_concurrent_sends, |connection| async move {
+                connection.send_frame_conflated(frame, conflation_key).await;
+            })
+            .await;
+    }
+}
@@ -1,6 +1,11 @@
 use std::{
+    collections::HashMap,
     net::SocketAddr,
-    sync::{atomic::AtomicBool, Arc},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize},
+        Arc,
+    },
+    time::Duration,
 };
 
 use hyper_tungstenite::tungstenite::Message;
@@ -10,16 +15,68 @@ use rust_extensions::{
     TaskCompletion,
 };
 
-use tokio::sync::Mutex;
+use futures::channel::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::sync::{oneshot, Mutex, MutexGuard, Notify};
 
 #[cfg(feature = "with-ctx")]
 use tokio::sync::RwLock;
 
-use crate::SignalRParam;
+use crate::{
+    messages::{HubProtocol, SignalrCompletionMessage},
+    OutboundQueue, OutboundQueuePushResult, OutboundQueueSettings, SignalRParam,
+    SignalrConnectionSettings, SignalrContractDeserializer, SignalrIdentity,
+    SignalrInvocationFrame, SignalrInvokeError, SignalrTransport, StatefulReconnectBuffer,
+    StatefulReconnectSettings,
+};
+
+const LONG_POOLING_QUEUE_MAX_SIZE: usize = 16 * 1024 * 1024;
 
 pub struct MySignalrConnectionSingleThreaded {
     web_socket: Option<Arc<MyWebSocket>>,
-    long_pooling: Option<TaskCompletion<String, String>>,
+    hub_protocol: HubProtocol,
+    long_pooling: Option<TaskCompletion<(), String>>,
+    long_pooling_attached: bool,
+    long_pooling_queue: Vec<u8>,
+    server_sent_events: Option<UnboundedSender<Vec<u8>>>,
+    invocations: HashMap<String, oneshot::Sender<Result<Vec<u8>, String>>>,
+    streams: HashMap<String, oneshot::Sender<()>>,
+    upload_streams: HashMap<String, UnboundedSender<Vec<u8>>>,
+    stateful_reconnect: Option<StatefulReconnectBuffer>,
+    detached_moment: Option<DateTimeAsMicroseconds>,
+    awaiting_replay: bool,
+    outbound_queue: Option<OutboundQueue>,
+}
+
+impl MySignalrConnectionSingleThreaded {
+    fn push_long_pooling_payload(&mut self, payload: &[u8]) -> bool {
+        if self.long_pooling_queue.len() + payload.len() > LONG_POOLING_QUEUE_MAX_SIZE {
+            return false;
+        }
+
+        self.long_pooling_queue.extend_from_slice(payload);
+
+        if let Some(mut long_pooling) = self.long_pooling.take() {
+            long_pooling.set_ok(());
+        }
+
+        true
+    }
+
+    fn get_transport(&self, in_web_socket_model: bool) -> Option<SignalrTransport> {
+        if self.web_socket.is_some() {
+            return Some(SignalrTransport::WebSocket);
+        }
+
+        if self.server_sent_events.is_some() {
+            return Some(SignalrTransport::ServerSentEvents);
+        }
+
+        if !in_web_socket_model && self.long_pooling_attached {
+            return Some(SignalrTransport::LongPolling);
+        }
+
+        None
+    }
 }
 
 pub struct MySignalrConnection<TCtx: Send + Sync + 'static> {
@@ -28,38 +85,77 @@ pub struct MySignalrConnection<TCtx: Send + Sync + 'static> {
     pub connection_token: Option<String>,
     pub created: DateTimeAsMicroseconds,
     pub last_incoming_moment: AtomicDateTimeAsMicroseconds,
+    pub last_outgoing_moment: AtomicDateTimeAsMicroseconds,
     connected: AtomicBool,
     has_web_socket: AtomicBool,
     has_greeting: AtomicBool,
+    invocation_id: AtomicU64,
+    liveness_loop_started: AtomicBool,
+    outbound_writer_started: AtomicBool,
+    outbound_queue_depth: AtomicUsize,
+    outbound_notify: Notify,
+    sequenced_send: Mutex<()>,
     pub negotiation_version: usize,
+    pub stateful_reconnect: Option<StatefulReconnectSettings>,
+    pub outbound_queue: Option<OutboundQueueSettings>,
+    pub identity: Option<SignalrIdentity>,
     pub ctx: TCtx,
 }
 
-impl<TCtx: Send + Sync + Default + 'static> MySignalrConnection<TCtx> {
+impl<TCtx: Send + Sync + 'static> MySignalrConnection<TCtx> {
     pub fn new(
         connection_id: String,
         connection_token: Option<String>,
-        negotiation_version: usize,
         web_socket: Option<Arc<MyWebSocket>>,
+        settings: SignalrConnectionSettings,
+        identity: Option<SignalrIdentity>,
+        ctx: TCtx,
     ) -> Self {
         let has_web_socket = web_socket.is_some();
         Self {
             single_threaded: Mutex::new(MySignalrConnectionSingleThreaded {
                 web_socket,
+                hub_protocol: HubProtocol::Json,
                 long_pooling: None,
+                long_pooling_attached: false,
+                long_pooling_queue: Vec::new(),
+                server_sent_events: None,
+                invocations: HashMap::new(),
+                streams: HashMap::new(),
+                upload_streams: HashMap::new(),
+                stateful_reconnect: settings
+                    .stateful_reconnect
+                    .map(|settings| StatefulReconnectBuffer::new(settings.buffer_size)),
+                detached_moment: None,
+                awaiting_replay: false,
+                outbound_queue: settings.outbound_queue.map(OutboundQueue::new),
             }),
             connection_id,
             connection_token,
-            negotiation_version,
+            negotiation_version: settings.negotiation_version,
             created: DateTimeAsMicroseconds::now(),
             last_incoming_moment: AtomicDateTimeAsMicroseconds::now(),
+            last_outgoing_moment: AtomicDateTimeAsMicroseconds::now(),
             connected: AtomicBool::new(true),
             has_web_socket: AtomicBool::new(has_web_socket),
             has_greeting: AtomicBool::new(false),
-            ctx: TCtx::default(),
+            invocation_id: AtomicU64::new(0),
+            liveness_loop_started: AtomicBool::new(false),
+            outbound_writer_started: AtomicBool::new(false),
+            outbound_queue_depth: AtomicUsize::new(0),
+            outbound_notify: Notify::new(),
+            sequenced_send: Mutex::new(()),
+            stateful_reconnect: settings.stateful_reconnect,
+            outbound_queue: settings.outbound_queue,
+            identity,
+            ctx,
         }
     }
 
+    pub fn user_id(&self) -> Option<&str> {
+        self.identity.as_ref()?.user_id.as_deref()
+    }
+
     pub fn get_list_index(&self) -> &String {
         if let Some(token) = self.connection_token.as_ref() {
             token
@@ -105,83 +201,556 @@ impl<TCtx: Send + Sync + Default + 'static> MySignalrConnection<TCtx> {
         self.last_incoming_moment.as_date_time()
     }
 
-    pub async fn send<'s>(&self, action_name: &str, parameter: &SignalRParam<'s>) {
-        let web_socket = {
-            let read_access = self.single_threaded.lock().await;
-            read_access.web_socket.clone()
-        };
+    pub fn update_outgoing_activity(&self) {
+        let now = DateTimeAsMicroseconds::now();
+        self.last_outgoing_moment.update(now);
+    }
+
+    pub fn get_last_outgoing(&self) -> DateTimeAsMicroseconds {
+        self.last_outgoing_moment.as_date_time()
+    }
 
-        if let Some(web_socket) = web_socket {
-            let mut result = Vec::new();
+    pub async fn set_hub_protocol(&self, hub_protocol: HubProtocol) {
+        let mut write_access = self.single_threaded.lock().await;
 
-            result.extend_from_slice("{\"type\":1,\"target\":\"".as_bytes());
-            result.extend_from_slice(action_name.as_bytes());
-            result.extend_from_slice("\",\"arguments\":[".as_bytes());
-            match parameter {
-                SignalRParam::JsonObject(json_writer) => {
-                    json_writer.build_into(&mut result);
-                }
-                SignalRParam::String(value) => {
-                    let json_string = my_json::EscapedJsonString::new(value);
-                    result.push(b'"');
-                    result.extend_from_slice(json_string.as_str().as_bytes());
-                    result.push(b'"');
-                }
-                SignalRParam::Number(number) => {
-                    result.extend_from_slice(number.to_string().as_bytes());
+        if write_access.hub_protocol != hub_protocol {
+            if let Some(outbound_queue) = write_access.outbound_queue.as_mut() {
+                outbound_queue.clear();
+                self.outbound_queue_depth
+                    .store(0, std::sync::atomic::Ordering::SeqCst);
+            }
+        }
+
+        write_access.hub_protocol = hub_protocol;
+    }
+
+    pub async fn get_hub_protocol(&self) -> HubProtocol {
+        let read_access = self.single_threaded.lock().await;
+        read_access.hub_protocol
+    }
+
+    async fn send_payload(
+        &self,
+        web_socket: &MyWebSocket,
+        hub_protocol: HubProtocol,
+        payload: Vec<u8>,
+    ) {
+        self.update_outgoing_activity();
+
+        match hub_protocol {
+            HubProtocol::Json => {
+                if let Ok(payload) = String::from_utf8(payload) {
+                    web_socket.send_message(Message::Text(payload)).await;
                 }
-                SignalRParam::Float(value) => {
-                    result.extend_from_slice(value.to_string().as_bytes());
+            }
+            HubProtocol::MessagePack => {
+                web_socket.send_message(Message::Binary(payload)).await;
+            }
+        }
+    }
+
+    async fn send_message_payload(
+        &self,
+        generate_payload: impl FnOnce(HubProtocol) -> Result<Vec<u8>, String>,
+    ) -> Result<(), String> {
+        let _sequenced_send = if self.stateful_reconnect.is_some() {
+            Some(self.sequenced_send.lock().await)
+        } else {
+            None
+        };
+
+        let mut write_access = self.single_threaded.lock().await;
+        let hub_protocol = write_access.hub_protocol;
+
+        let payload = match generate_payload(hub_protocol) {
+            Ok(payload) => payload,
+            Err(err) => {
+                #[cfg(feature = "debug_ws")]
+                println!(
+                    "Can not send payload to connection {}: {}",
+                    self.connection_id, err
+                );
+                return Err(err);
+            }
+        };
+
+        if let Some(stateful_reconnect) = write_access.stateful_reconnect.as_mut() {
+            stateful_reconnect.push(payload.clone());
+
+            if write_access.outbound_queue.is_none() {
+                if let Some(web_socket) = write_access.web_socket.clone() {
+                    drop(write_access);
+                    self.send_payload(&web_socket, hub_protocol, payload).await;
+                    return Ok(());
                 }
-                SignalRParam::Boolean(value) => {
-                    if *value {
-                        result.extend_from_slice("true".as_bytes());
+            }
+        }
+
+        self.send_to_transport(write_access, hub_protocol, payload, None)
+            .await;
+        Ok(())
+    }
+
+    async fn send_control_payload(
+        &self,
+        write_access: MutexGuard<'_, MySignalrConnectionSingleThreaded>,
+        hub_protocol: HubProtocol,
+        payload: Vec<u8>,
+    ) {
+        if let Some(web_socket) = write_access.web_socket.clone() {
+            drop(write_access);
+            self.send_payload(&web_socket, hub_protocol, payload).await;
+            return;
+        }
+
+        self.send_to_transport(write_access, hub_protocol, payload, None)
+            .await;
+    }
+
+    async fn send_to_transport(
+        &self,
+        mut write_access: MutexGuard<'_, MySignalrConnectionSingleThreaded>,
+        hub_protocol: HubProtocol,
+        payload: Vec<u8>,
+        conflation_key: Option<&str>,
+    ) {
+        match write_access.get_transport(self.in_web_socket_model()) {
+            Some(SignalrTransport::WebSocket) => {
+                if let Some(outbound_queue) = write_access.outbound_queue.as_mut() {
+                    let push_result = outbound_queue.push(conflation_key, payload);
+                    self.outbound_queue_depth
+                        .store(outbound_queue.len(), std::sync::atomic::Ordering::SeqCst);
+                    drop(write_access);
+
+                    if push_result == OutboundQueuePushResult::Overflowed {
+                        #[cfg(feature = "debug_ws")]
+                        println!(
+                            "Signalr {} outbound queue is overflowed. Disconnecting slow consumer",
+                            self.connection_id
+                        );
+                        self.disconnect().await;
                     } else {
-                        result.extend_from_slice("false".as_bytes());
+                        self.outbound_notify.notify_one();
                     }
+
+                    return;
                 }
-                SignalRParam::Raw(value) => {
-                    for (index, item) in value.iter().enumerate() {
-                        if index > 0 {
-                            result.push(b',');
-                        }
-                        result.extend_from_slice(item.as_slice());
-                    }
+
+                let web_socket = write_access.web_socket.clone().unwrap();
+                drop(write_access);
+                self.send_payload(&web_socket, hub_protocol, payload).await;
+            }
+            Some(SignalrTransport::ServerSentEvents) => {
+                if let Some(sender) = write_access.server_sent_events.as_ref() {
+                    self.update_outgoing_activity();
+                    let _ = sender
+                        .unbounded_send(crate::messages::generate_server_sent_event(&payload));
                 }
-                SignalRParam::None => {}
             }
+            Some(SignalrTransport::LongPolling) if self.is_connected() => {
+                if !write_access.push_long_pooling_payload(&payload) {
+                    drop(write_access);
+                    #[cfg(feature = "debug_ws")]
+                    println!(
+                        "Signalr {} long polling queue is overflowed. Disconnecting slow consumer",
+                        self.connection_id
+                    );
+                    self.disconnect().await;
+                    return;
+                }
+
+                self.update_outgoing_activity();
+            }
+            Some(SignalrTransport::LongPolling) | None => {}
+        }
+    }
 
-            result.extend_from_slice("]}".as_bytes());
-            result.push(30);
+    pub(crate) async fn send_next_outbound_payload(&self, max_batch_size: usize) -> bool {
+        let mut write_access = self.single_threaded.lock().await;
+        let hub_protocol = write_access.hub_protocol;
 
-            web_socket
-                .send_message(Message::Text(String::from_utf8(result).unwrap()))
-                .await;
+        let web_socket = match write_access.web_socket.clone() {
+            Some(web_socket) => web_socket,
+            None => return false,
+        };
+
+        let outbound_queue = match write_access.outbound_queue.as_mut() {
+            Some(outbound_queue) => outbound_queue,
+            None => return false,
+        };
+
+        let payload = match outbound_queue.pop_batch(max_batch_size) {
+            Some(payload) => payload,
+            None => return false,
+        };
+
+        self.outbound_queue_depth
+            .store(outbound_queue.len(), std::sync::atomic::Ordering::SeqCst);
+
+        drop(write_access);
+
+        self.send_payload(&web_socket, hub_protocol, payload).await;
+        true
+    }
+
+    pub(crate) async fn wait_for_outbound_payload(&self) {
+        self.outbound_notify.notified().await;
+    }
+
+    pub fn get_outbound_queue_depth(&self) -> usize {
+        self.outbound_queue_depth
+            .load(std::sync::atomic::Ordering::SeqCst)
+    }
+
+    pub async fn get_transport(&self) -> Option<SignalrTransport> {
+        let read_access = self.single_threaded.lock().await;
+        read_access.get_transport(self.in_web_socket_model())
+    }
+
+    pub async fn attach_server_sent_events(&self, sender: UnboundedSender<Vec<u8>>) -> bool {
+        let mut write_access = self.single_threaded.lock().await;
+
+        if let Some(current_sender) = write_access.server_sent_events.as_ref() {
+            if !current_sender.is_closed() {
+                return false;
+            }
+        }
+
+        if !write_access.long_pooling_queue.is_empty() {
+            let payload = std::mem::take(&mut write_access.long_pooling_queue);
+            let _ = sender.unbounded_send(crate::messages::generate_server_sent_event(&payload));
         }
+
+        write_access.server_sent_events = Some(sender);
+        true
+    }
+
+    pub(crate) async fn attach_long_pooling(&self) -> bool {
+        let mut write_access = self.single_threaded.lock().await;
+
+        if write_access.long_pooling_attached {
+            return false;
+        }
+
+        write_access.long_pooling_attached = true;
+        true
+    }
+
+    pub async fn long_pool(&self, poll_timeout: Duration) -> Option<Vec<u8>> {
+        let awaiter = {
+            let mut write_access = self.single_threaded.lock().await;
+
+            if !write_access.long_pooling_queue.is_empty() {
+                return Some(std::mem::take(&mut write_access.long_pooling_queue));
+            }
+
+            if !self.is_connected() {
+                return None;
+            }
+
+            let mut long_pooling = TaskCompletion::new();
+            let awaiter = long_pooling.get_awaiter();
+
+            if let Some(mut prev_long_pooling) = write_access.long_pooling.replace(long_pooling) {
+                prev_long_pooling.set_ok(());
+            }
+
+            awaiter
+        };
+
+        let _ = tokio::time::timeout(poll_timeout, awaiter.get_result()).await;
+
+        let mut write_access = self.single_threaded.lock().await;
+        write_access.long_pooling.take();
+
+        if write_access.long_pooling_queue.is_empty() && !self.is_connected() {
+            return None;
+        }
+
+        Some(std::mem::take(&mut write_access.long_pooling_queue))
+    }
+
+    pub async fn send<'s>(&self, action_name: &str, parameter: &SignalRParam<'s>) {
+        self.send_with_args(action_name, std::slice::from_ref(parameter))
+            .await;
+    }
+
+    pub async fn send_with_args<'s>(&self, action_name: &str, parameters: &[SignalRParam<'s>]) {
+        let _ = self
+            .send_message_payload(|hub_protocol| match hub_protocol {
+                HubProtocol::Json => Ok(crate::messages::generate_invocation_payload(
+                    None,
+                    action_name,
+                    parameters,
+                )),
+                HubProtocol::MessagePack => crate::messages::generate_msgpack_invocation_payload(
+                    None,
+                    action_name,
+                    parameters,
+                ),
+            })
+            .await;
+    }
+
+    pub async fn send_frame(&self, frame: &SignalrInvocationFrame) {
+        let _ = self
+            .send_message_payload(|hub_protocol| {
+                frame
+                    .get(hub_protocol)
+                    .map(|payload| payload.to_vec())
+                    .map_err(|err| err.to_string())
+            })
+            .await;
+    }
+
+    /// Falls back to `send_frame` unless the connection is a WebSocket with an outbound queue
+    /// and without stateful reconnect, since replacing sequenced messages breaks the replay.
+    pub async fn send_frame_conflated(&self, frame: &SignalrInvocationFrame, conflation_key: &str) {
+        let write_access = self.single_threaded.lock().await;
+
+        if write_access.stateful_reconnect.is_some() {
+            drop(write_access);
+            self.send_frame(frame).await;
+            return;
+        }
+
+        let hub_protocol = write_access.hub_protocol;
+        let payload = match frame.get(hub_protocol) {
+            Ok(payload) => payload.to_vec(),
+            Err(_err) => {
+                #[cfg(feature = "debug_ws")]
+                println!(
+                    "Can not send payload to connection {}: {}",
+                    self.connection_id, _err
+                );
+                return;
+            }
+        };
+        self.send_to_transport(write_access, hub_protocol, payload, Some(conflation_key))
+            .await;
+    }
+
+    pub async fn send_completion(
+        &self,
+        invocation_id: &str,
+        result: &Result<Vec<Vec<u8>>, String>,
+    ) {
+        let _ = self
+            .send_message_payload(|hub_protocol| match hub_protocol {
+                HubProtocol::Json => Ok(crate::messages::generate_completion_payload(
+                    invocation_id,
+                    result,
+                )),
+                HubProtocol::MessagePack => Ok(
+                    crate::messages::generate_msgpack_completion_payload(invocation_id, result),
+                ),
+            })
+            .await;
+    }
+
+    pub async fn invoke<'s, TResult: SignalrContractDeserializer<Item = TResult>>(
+        &self,
+        action_name: &str,
+        parameter: &SignalRParam<'s>,
+        timeout: Duration,
+    ) -> Result<TResult, SignalrInvokeError> {
+        self.invoke_with_args(action_name, std::slice::from_ref(parameter), timeout)
+            .await
+    }
+
+    pub async fn invoke_with_args<'s, TResult: SignalrContractDeserializer<Item = TResult>>(
+        &self,
+        action_name: &str,
+        parameters: &[SignalRParam<'s>],
+        timeout: Duration,
+    ) -> Result<TResult, SignalrInvokeError> {
+        let invocation_id = self
+            .invocation_id
+            .fetch_add(1, std::sync::atomic::Ordering::SeqCst)
+            .to_string();
+
+        if !self.is_connected() {
+            return Err(SignalrInvokeError::Disconnected);
+        }
+
+        let receiver = {
+            let mut write_access = self.single_threaded.lock().await;
+
+            let (sender, receiver) = oneshot::channel();
+            write_access
+                .invocations
+                .insert(invocation_id.clone(), sender);
+
+            receiver
+        };
+
+        let send_result = self
+            .send_message_payload(|hub_protocol| match hub_protocol {
+                HubProtocol::Json => Ok(crate::messages::generate_invocation_payload(
+                    Some(invocation_id.as_str()),
+                    action_name,
+                    parameters,
+                )),
+                HubProtocol::MessagePack => crate::messages::generate_msgpack_invocation_payload(
+                    Some(invocation_id.as_str()),
+                    action_name,
+                    parameters,
+                ),
+            })
+            .await;
+
+        if let Err(err) = send_result {
+            let mut write_access = self.single_threaded.lock().await;
+            write_access.invocations.remove(invocation_id.as_str());
+            return Err(SignalrInvokeError::SerializeError(err));
+        }
+
+        let result = match tokio::time::timeout(timeout, receiver).await {
+            Ok(result) => result,
+            Err(_) => {
+                let mut write_access = self.single_threaded.lock().await;
+                write_access.invocations.remove(invocation_id.as_str());
+                return Err(SignalrInvokeError::Timeout);
+            }
+        };
+
+        match result {
+            Ok(Ok(result)) => TResult::deserialize(&[result.as_slice()])
+                .map_err(SignalrInvokeError::DeserializeError),
+            Ok(Err(err)) => Err(SignalrInvokeError::ClientError(err)),
+            Err(_) => Err(SignalrInvokeError::Disconnected),
+        }
+    }
+
+    pub async fn set_invocation_result<'s>(&self, completion: &SignalrCompletionMessage<'s>) {
+        let sender = {
+            let mut write_access = self.single_threaded.lock().await;
+            write_access.invocations.remove(completion.invocation_id)
+        };
+
+        if let Some(sender) = sender {
+            let result = match completion.error {
+                Some(err) => Err(err.to_string()),
+                None => Ok(completion.result.unwrap_or(b"null").to_vec()),
+            };
+
+            let _ = sender.send(result);
+        }
+    }
+
+    pub async fn send_stream_item(&self, invocation_id: &str, item: &[Vec<u8>]) {
+        let _ = self
+            .send_message_payload(|hub_protocol| match hub_protocol {
+                HubProtocol::Json => Ok(crate::messages::generate_stream_item_payload(
+                    invocation_id,
+                    item,
+                )),
+                HubProtocol::MessagePack => {
+                    crate::messages::generate_msgpack_stream_item_payload(invocation_id, item)
+                }
+            })
+            .await;
+    }
+
+    pub async fn register_stream(&self, invocation_id: &str) -> oneshot::Receiver<()> {
+        let (sender, receiver) = oneshot::channel();
+        let mut write_access = self.single_threaded.lock().await;
+        write_access
+            .streams
+            .insert(invocation_id.to_string(), sender);
+        receiver
+    }
+
+    pub async fn unregister_stream(&self, invocation_id: &str) {
+        let mut write_access = self.single_threaded.lock().await;
+        write_access.streams.remove(invocation_id);
+    }
+
+    pub async fn cancel_stream(&self, invocation_id: &str) {
+        let sender = {
+            let mut write_access = self.single_threaded.lock().await;
+            write_access.streams.remove(invocation_id)
+        };
+
+        if let Some(sender) = sender {
+            let _ = sender.send(());
+        }
+    }
+
+    pub async fn register_upload_stream(&self, stream_id: &str) -> UnboundedReceiver<Vec<u8>> {
+        let (sender, receiver) = mpsc::unbounded();
+        let mut write_access = self.single_threaded.lock().await;
+        write_access
+            .upload_streams
+            .insert(stream_id.to_string(), sender);
+        receiver
+    }
+
+    pub async fn push_upload_stream_item(&self, stream_id: &str, item: &[u8]) {
+        let read_access = self.single_threaded.lock().await;
+        if let Some(sender) = read_access.upload_streams.get(stream_id) {
+            let _ = sender.unbounded_send(item.to_vec());
+        }
+    }
+
+    pub async fn complete_upload_stream(&self, stream_id: &str) -> bool {
+        let mut write_access = self.single_threaded.lock().await;
+        write_access.upload_streams.remove(stream_id).is_some()
     }
 
     pub async fn send_ping_payload(&self) {
-        self.send_raw_payload(crate::messages::get_ping_payload().to_string())
+        if !self.get_has_greeting() {
+            return;
+        }
+
+        let write_access = self.single_threaded.lock().await;
+        let hub_protocol = write_access.hub_protocol;
+
+        let payload = match hub_protocol {
+            HubProtocol::Json => {
+                let mut payload = crate::messages::get_ping_payload().as_bytes().to_vec();
+                payload.push(30);
+                payload
+            }
+            HubProtocol::MessagePack => crate::messages::generate_msgpack_ping_payload(),
+        };
+
+        self.send_control_payload(write_access, hub_protocol, payload)
             .await;
     }
 
     pub async fn send_raw_payload(&self, mut raw_payload: String) {
-        let web_socket = {
-            let read_access = self.single_threaded.lock().await;
-            read_access.web_socket.clone()
-        };
+        let write_access = self.single_threaded.lock().await;
 
         raw_payload.push(30 as char);
 
-        if let Some(web_socket) = web_socket {
-            web_socket.send_message(Message::Text(raw_payload)).await;
-        }
+        self.send_control_payload(write_access, HubProtocol::Json, raw_payload.into_bytes())
+            .await;
     }
 
     pub async fn add_web_socket(&self, web_socket: Arc<MyWebSocket>) {
         let new_id = web_socket.id;
         let mut write_access = self.single_threaded.lock().await;
 
+        write_access.detached_moment = None;
+        write_access.long_pooling_queue.clear();
+
+        if write_access.stateful_reconnect.is_some() && self.get_has_greeting() {
+            write_access.awaiting_replay = true;
+
+            if let Some(outbound_queue) = write_access.outbound_queue.as_mut() {
+                outbound_queue.clear();
+                self.outbound_queue_depth
+                    .store(0, std::sync::atomic::Ordering::SeqCst);
+            }
+        }
+
+        self.has_greeting
+            .store(false, std::sync::atomic::Ordering::SeqCst);
+        self.has_web_socket
+            .store(true, std::sync::atomic::Ordering::SeqCst);
+        self.update_incoming_activity();
+
         if let Some(old_websocket) = write_access.web_socket.replace(web_socket) {
             old_websocket
                 .send_message(hyper_tungstenite::tungstenite::Message::Text(format!(
@@ -190,6 +759,164 @@ impl<TCtx: Send + Sync + Default + 'static> MySignalrConnection<TCtx> {
                 )))
                 .await;
         }
+
+        self.outbound_notify.notify_one();
+    }
+
+    pub async fn detach_web_socket(&self, web_socket_id: i64) -> bool {
+        let mut write_access = self.single_threaded.lock().await;
+
+        match write_access.web_socket.as_ref() {
+            Some(web_socket) if web_socket.id == web_socket_id => {}
+            _ => return false,
+        }
+
+        write_access.web_socket.take();
+        write_access.detached_moment = Some(DateTimeAsMicroseconds::now());
+        true
+    }
+
+    pub async fn get_detached_moment(&self) -> Option<DateTimeAsMicroseconds> {
+        let read_access = self.single_threaded.lock().await;
+        read_access.detached_moment
+    }
+
+    pub fn try_start_liveness_loop(&self) -> bool {
+        self.liveness_loop_started
+            .compare_exchange(
+                false,
+                true,
+                std::sync::atomic::Ordering::SeqCst,
+                std::sync::atomic::Ordering::SeqCst,
+            )
+            .is_ok()
+    }
+
+    pub fn try_start_outbound_writer(&self) -> bool {
+        if self.outbound_queue.is_none() {
+            return false;
+        }
+
+        self.outbound_writer_started
+            .compare_exchange(
+                false,
+                true,
+                std::sync::atomic::Ordering::SeqCst,
+                std::sync::atomic::Ordering::SeqCst,
+            )
+            .is_ok()
+    }
+
+    pub async fn accept_incoming_message(&self) -> bool {
+        let mut write_access = self.single_threaded.lock().await;
+        match write_access.stateful_reconnect.as_mut() {
+            Some(stateful_reconnect) => stateful_reconnect.accept_incoming(),
+            None => true,
+        }
+    }
+
+    pub async fn ack(&self, sequence_id: u64) {
+        let mut write_access = self.single_threaded.lock().await;
+        if let Some(stateful_reconnect) = write_access.stateful_reconnect.as_mut() {
+            stateful_reconnect.ack(sequence_id);
+        }
+    }
+
+    pub async fn set_incoming_sequence(&self, sequence_id: u64) -> bool {
+        let mut write_access = self.single_threaded.lock().await;
+        match write_access.stateful_reconnect.as_mut() {
+            Some(stateful_reconnect) => stateful_reconnect.set_incoming_sequence(sequence_id),
+            None => true,
+        }
+    }
+
+    pub async fn send_ack_if_needed(&self) {
+        let mut write_access = self.single_threaded.lock().await;
+        let hub_protocol = write_access.hub_protocol;
+
+        let web_socket = match write_access.web_socket.clone() {
+            Some(web_socket) => web_socket,
+            None => return,
+        };
+
+        let sequence_id = match write_access.stateful_reconnect.as_mut() {
+            Some(stateful_reconnect) => stateful_reconnect.get_ack_to_send(),
+            None => None,
+        };
+
+        drop(write_access);
+
+        if let Some(sequence_id) = sequence_id {
+            let payload = match hub_protocol {
+                HubProtocol::Json => crate::messages::generate_ack_payload(sequence_id),
+                HubProtocol::MessagePack => {
+                    crate::messages::generate_msgpack_ack_payload(sequence_id)
+                }
+            };
+
+            self.send_payload(&web_socket, hub_protocol, payload).await;
+        }
+    }
+
+    pub async fn replay_unacknowledged(&self) -> Result<(), String> {
+        let _sequenced_send = self.sequenced_send.lock().await;
+        let mut write_access = self.single_threaded.lock().await;
+
+        if !write_access.awaiting_replay {
+            return Ok(());
+        }
+
+        write_access.awaiting_replay = false;
+
+        let hub_protocol = write_access.hub_protocol;
+
+        let (first_sequence_id, payloads) = match write_access.stateful_reconnect.as_ref() {
+            Some(stateful_reconnect) => stateful_reconnect.get_unacknowledged()?,
+            None => return Ok(()),
+        };
+
+        let web_socket = match write_access.web_socket.clone() {
+            Some(web_socket) => web_socket,
+            None => return Ok(()),
+        };
+
+        drop(write_access);
+
+        let sequence_payload = match hub_protocol {
+            HubProtocol::Json => crate::messages::generate_sequence_payload(first_sequence_id),
+            HubProtocol::MessagePack => {
+                crate::messages::generate_msgpack_sequence_payload(first_sequence_id)
+            }
+        };
+
+        self.send_payload(&web_socket, hub_protocol, sequence_payload)
+            .await;
+
+        for payload in payloads {
+            self.send_payload(&web_socket, hub_protocol, payload).await;
+        }
+
+        Ok(())
+    }
+
+    pub async fn close(&self, error: Option<&str>, allow_reconnect: bool) {
+        self.send_close(error, allow_reconnect).await;
+        self.disconnect().await;
+    }
+
+    pub(crate) async fn send_close(&self, error: Option<&str>, allow_reconnect: bool) {
+        let write_access = self.single_threaded.lock().await;
+        let hub_protocol = write_access.hub_protocol;
+
+        let payload = match hub_protocol {
+            HubProtocol::Json => crate::messages::generate_close_payload(error, allow_reconnect),
+            HubProtocol::MessagePack => {
+                crate::messages::generate_msgpack_close_payload(error, allow_reconnect)
+            }
+        };
+
+        self.send_control_payload(write_access, hub_protocol, payload)
+            .await;
     }
 
     pub async fn disconnect(&self) -> Option<Arc<MyWebSocket>> {
@@ -209,6 +936,20 @@ impl<TCtx: Send + Sync + Default + 'static> MySignalrConnection<TCtx> {
             long_pooling.set_error(format!("Canceling this LongPool since we disconnect it."));
         }
 
+        write_access.server_sent_events.take();
+
+        if let Some(outbound_queue) = write_access.outbound_queue.as_mut() {
+            outbound_queue.clear();
+            self.outbound_queue_depth
+                .store(0, std::sync::atomic::Ordering::SeqCst);
+        }
+
+        self.outbound_notify.notify_one();
+
+        write_access.invocations.clear();
+        write_access.streams.clear();
+        write_access.upload_streams.clear();
+
         result
     }
 
@@ -223,3 +964,134 @@ impl<TCtx: Send + Sync + Default + 'static> MySignalrConnection<TCtx> {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use super::MySignalrConnection;
+    use crate::{SignalRParam, SignalrConnectionSettings};
+
+    fn create_connection() -> Arc<MySignalrConnection<()>> {
+        Arc::new(MySignalrConnection::new(
+            "connection".to_string(),
+            None,
+            None,
+            SignalrConnectionSettings {
+                negotiation_version: 0,
+                stateful_reconnect: None,
+                outbound_queue: None,
+            },
+            None,
+            (),
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_long_polling_buffers_only_after_attach() {
+        let connection = create_connection();
+
+        connection.send("before", &SignalRParam::None).await;
+
+        assert!(connection.attach_long_pooling().await);
+        assert!(!connection.attach_long_pooling().await);
+
+        connection.send("after", &SignalRParam::None).await;
+
+        let payload = connection
+            .long_pool(Duration::from_millis(10))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            "{\"type\":1,\"target\":\"after\",\"arguments\":[]}\u{1e}",
+            String::from_utf8(payload).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_long_polling_wakes_pending_poll() {
+        let connection = create_connection();
+        connection.attach_long_pooling().await;
+
+        let poll = {
+            let connection = connection.clone();
+            tokio::spawn(async move { connection.long_pool(Duration::from_secs(5)).await })
+        };
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        connection.send("update", &SignalRParam::None).await;
+
+        let payload = tokio::time::timeout(Duration::from_secs(1), poll)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+
+        assert!(!payload.is_empty());
+
+        let drained = connection
+            .long_pool(Duration::from_millis(10))
+            .await
+            .unwrap();
+        assert!(drained.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_long_polling_returns_none_after_disconnect() {
+        let connection = create_connection();
+        connection.attach_long_pooling().await;
+
+        connection.disconnect().await;
+
+        assert!(connection
+            .long_pool(Duration::from_millis(10))
+            .await
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_long_polling_overflow_disconnects() {
+        let connection = create_connection();
+        connection.attach_long_pooling().await;
+
+        let payload = "a".repeat(super::LONG_POOLING_QUEUE_MAX_SIZE);
+        connection
+            .send("update", &SignalRParam::String(payload.as_str()))
+            .await;
+
+        assert!(!connection.is_connected());
+    }
+
+    #[tokio::test]
+    async fn test_server_sent_events_framing() {
+        let connection = create_connection();
+
+        let (sender, mut receiver) = futures::channel::mpsc::unbounded();
+        assert!(connection.attach_server_sent_events(sender).await);
+
+        connection.send("update", &SignalRParam::None).await;
+
+        let event = receiver.try_recv().unwrap();
+        assert_eq!(
+            "data: {\"type\":1,\"target\":\"update\",\"arguments\":[]}\u{1e}\r\n\r\n",
+            String::from_utf8(event).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_second_server_sent_events_stream_is_rejected() {
+        let connection = create_connection();
+
+        let (sender, receiver) = futures::channel::mpsc::unbounded();
+        assert!(connection.attach_server_sent_events(sender).await);
+
+        let (second_sender, _second_receiver) = futures::channel::mpsc::unbounded();
+        assert!(!connection.attach_server_sent_events(second_sender).await);
+
+        drop(receiver);
+
+        let (third_sender, _third_receiver) = futures::channel::mpsc::unbounded();
+        assert!(connection.attach_server_sent_events(third_sender).await);
+    }
+}
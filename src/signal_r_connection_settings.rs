@@ -0,0 +1,8 @@
+use crate::{OutboundQueueSettings, StatefulReconnectSettings};
+
+#[derive(Debug, Clone, Copy)]
+pub struct SignalrConnectionSettings {
+    pub negotiation_version: usize,
+    pub stateful_reconnect: Option<StatefulReconnectSettings>,
+    pub outbound_queue: Option<OutboundQueueSettings>,
+}
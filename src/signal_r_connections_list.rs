@@ -1,33 +1,69 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    sync::{Arc, OnceLock},
+};
 
 use my_http_server_web_sockets::MyWebSocket;
 use rust_extensions::lazy::LazyVec;
 use tokio::sync::RwLock;
 
-use crate::MySignalrConnection;
+use crate::{MySignalrCallbacks, MySignalrConnection, TagQuery};
 
 struct SignalrListInner<TCtx: Send + Sync + 'static> {
     sockets_by_web_socket_id: HashMap<i64, Arc<MySignalrConnection<TCtx>>>,
     sockets_by_connection_token: HashMap<String, Arc<MySignalrConnection<TCtx>>>,
+    sockets_by_user_id: HashMap<String, HashMap<String, Arc<MySignalrConnection<TCtx>>>>,
     tags: crate::Tags,
+    groups: crate::Groups,
 }
 
-pub struct SignalrConnectionsList<TCtx: Send + Sync + Default + 'static> {
+impl<TCtx: Send + Sync + 'static> SignalrListInner<TCtx> {
+    fn get_connections_by_ids(
+        &self,
+        id_s: &[String],
+    ) -> Option<Vec<Arc<MySignalrConnection<TCtx>>>> {
+        let mut result = LazyVec::new();
+
+        for id in id_s {
+            if let Some(connection) = self.sockets_by_connection_token.get(id) {
+                result.add(connection.clone());
+            }
+        }
+
+        result.get_result()
+    }
+}
+
+pub struct SignalrConnectionsList<TCtx: Send + Sync + 'static> {
     sockets: RwLock<SignalrListInner<TCtx>>,
+    callbacks: OnceLock<Arc<dyn MySignalrCallbacks<TCtx = TCtx> + Send + Sync + 'static>>,
 }
 
-impl<TCtx: Send + Sync + Default + 'static> SignalrConnectionsList<TCtx> {
+impl<TCtx: Send + Sync + 'static> SignalrConnectionsList<TCtx> {
     pub fn new() -> Self {
         Self {
             sockets: RwLock::new(SignalrListInner {
                 sockets_by_web_socket_id: HashMap::new(),
                 sockets_by_connection_token: HashMap::new(),
+                sockets_by_user_id: HashMap::new(),
                 tags: crate::Tags::new(),
+                groups: crate::Groups::new(),
             }),
+            callbacks: OnceLock::new(),
         }
     }
 
-    pub async fn add_signalr_connection(&self, signalr_connection: Arc<MySignalrConnection<TCtx>>) {
+    pub(crate) fn set_callbacks(
+        &self,
+        callbacks: Arc<dyn MySignalrCallbacks<TCtx = TCtx> + Send + Sync + 'static>,
+    ) {
+        let _ = self.callbacks.set(callbacks);
+    }
+
+    pub async fn add_signalr_connection(
+        &self,
+        signalr_connection: Arc<MySignalrConnection<TCtx>>,
+    ) -> bool {
         let web_socket = signalr_connection.get_web_socket().await;
         let mut write_access = self.sockets.write().await;
         write_access.sockets_by_connection_token.insert(
@@ -35,11 +71,29 @@ impl<TCtx: Send + Sync + Default + 'static> SignalrConnectionsList<TCtx> {
             signalr_connection.clone(),
         );
 
+        let mut first_user_connection = false;
+
+        if let Some(user_id) = signalr_connection.user_id() {
+            let user_connections = write_access
+                .sockets_by_user_id
+                .entry(user_id.to_string())
+                .or_default();
+
+            user_connections.insert(
+                signalr_connection.get_list_index().to_string(),
+                signalr_connection.clone(),
+            );
+
+            first_user_connection = user_connections.len() == 1;
+        }
+
         if let Some(web_socket) = web_socket {
             write_access
                 .sockets_by_web_socket_id
                 .insert(web_socket.id, signalr_connection);
         }
+
+        first_user_connection
     }
 
     pub async fn assign_web_socket(
@@ -79,6 +133,21 @@ impl<TCtx: Send + Sync + Default + 'static> SignalrConnectionsList<TCtx> {
         }
     }
 
+    pub async fn detach_web_socket(
+        &self,
+        web_socket_id: i64,
+    ) -> Option<Arc<MySignalrConnection<TCtx>>> {
+        let removed = {
+            let mut write_access = self.sockets.write().await;
+            write_access
+                .sockets_by_web_socket_id
+                .remove(&web_socket_id)?
+        };
+
+        removed.detach_web_socket(web_socket_id).await;
+        Some(removed)
+    }
+
     pub async fn get_by_connection_token(
         &self,
         connection_token: &str,
@@ -147,30 +216,152 @@ impl<TCtx: Send + Sync + Default + 'static> SignalrConnectionsList<TCtx> {
     }
 
     pub async fn remove(&self, connection_token: &str) -> Option<Arc<MySignalrConnection<TCtx>>> {
-        let removed_signalr_connection = {
+        let (removed_signalr_connection, _) = self.remove_connection(connection_token).await?;
+        Some(removed_signalr_connection)
+    }
+
+    pub(crate) async fn remove_connection(
+        &self,
+        connection_token: &str,
+    ) -> Option<(Arc<MySignalrConnection<TCtx>>, bool)> {
+        let (removed_signalr_connection, last_user_connection) = {
             let mut write_access = self.sockets.write().await;
             let removed = write_access
                 .sockets_by_connection_token
-                .remove(connection_token);
+                .remove(connection_token)?;
+
+            write_access.tags.remove_connection(connection_token);
+            write_access.groups.remove_connection(connection_token);
 
-            if let Some(removed) = &removed {
-                write_access.tags.remove_connection(&removed.connection_id);
+            let mut last_user_connection = false;
+
+            if let Some(user_id) = removed.user_id() {
+                if let Some(user_connections) = write_access.sockets_by_user_id.get_mut(user_id) {
+                    if user_connections.remove(connection_token).is_some() {
+                        last_user_connection = user_connections.is_empty();
+                    }
+
+                    if user_connections.is_empty() {
+                        write_access.sockets_by_user_id.remove(user_id);
+                    }
+                }
             }
 
-            removed
+            (removed, last_user_connection)
         };
 
-        if let Some(removed_signalr_connection) = &removed_signalr_connection {
-            let web_socket = removed_signalr_connection.disconnect().await;
-            if let Some(web_socket) = web_socket {
-                let mut write_access = self.sockets.write().await;
-                write_access.sockets_by_web_socket_id.remove(&web_socket.id);
+        let web_socket = removed_signalr_connection.disconnect().await;
+        if let Some(web_socket) = web_socket {
+            let mut write_access = self.sockets.write().await;
+            write_access.sockets_by_web_socket_id.remove(&web_socket.id);
+        }
+
+        Some((removed_signalr_connection, last_user_connection))
+    }
+
+    pub async fn get_user_connections(
+        &self,
+        user_id: &str,
+    ) -> Option<Vec<Arc<MySignalrConnection<TCtx>>>> {
+        let read_access = self.sockets.read().await;
+        let user_connections = read_access.sockets_by_user_id.get(user_id)?;
+        Some(user_connections.values().cloned().collect())
+    }
+
+    pub async fn get_users_connections(
+        &self,
+        user_ids: &[&str],
+    ) -> Option<Vec<Arc<MySignalrConnection<TCtx>>>> {
+        let read_access = self.sockets.read().await;
+        let mut result = HashMap::new();
+
+        for user_id in user_ids {
+            if let Some(user_connections) = read_access.sockets_by_user_id.get(*user_id) {
+                for (connection_token, connection) in user_connections {
+                    result.insert(connection_token.as_str(), connection.clone());
+                }
             }
-        } else {
+        }
+
+        if result.is_empty() {
             return None;
         }
 
-        removed_signalr_connection
+        Some(result.into_values().collect())
+    }
+
+    pub async fn is_user_connected(&self, user_id: &str) -> bool {
+        let read_access = self.sockets.read().await;
+        read_access.sockets_by_user_id.contains_key(user_id)
+    }
+
+    pub async fn close(
+        &self,
+        connection_token: &str,
+        error: Option<&str>,
+        allow_reconnect: bool,
+    ) -> Option<Arc<MySignalrConnection<TCtx>>> {
+        let connection = self.get_by_connection_token(connection_token).await?;
+        connection.send_close(error, allow_reconnect).await;
+
+        match self.callbacks.get() {
+            Some(callbacks) => {
+                crate::process_disconnect(self, connection_token, callbacks).await;
+            }
+            None => {
+                self.remove_connection(connection_token).await;
+            }
+        }
+
+        Some(connection)
+    }
+
+    pub async fn add_connection_to_group(&self, ctx: &MySignalrConnection<TCtx>, group: &str) {
+        let mut write_access = self.sockets.write().await;
+
+        if write_access
+            .sockets_by_connection_token
+            .contains_key(ctx.get_list_index())
+        {
+            write_access
+                .groups
+                .add_to_group(ctx.get_list_index(), group);
+        }
+    }
+
+    pub async fn remove_connection_from_group(&self, ctx: &MySignalrConnection<TCtx>, group: &str) {
+        let mut write_access = self.sockets.write().await;
+        write_access
+            .groups
+            .remove_from_group(ctx.get_list_index(), group);
+    }
+
+    pub async fn get_connection_groups(
+        &self,
+        ctx: &MySignalrConnection<TCtx>,
+    ) -> Option<Vec<String>> {
+        let read_access = self.sockets.read().await;
+        read_access
+            .groups
+            .get_connection_groups(ctx.get_list_index())
+    }
+
+    pub async fn get_group_connections(
+        &self,
+        group: &str,
+    ) -> Option<Vec<Arc<MySignalrConnection<TCtx>>>> {
+        let read_access = self.sockets.read().await;
+        let id_s = read_access.groups.get_group_connections(group)?;
+        read_access.get_connections_by_ids(&id_s)
+    }
+
+    pub async fn get_groups_connections(
+        &self,
+        groups: &[&str],
+    ) -> Option<Vec<Arc<MySignalrConnection<TCtx>>>> {
+        let read_access = self.sockets.read().await;
+        let id_s = read_access.groups.get_groups_connections(groups)?;
+        read_access.get_connections_by_ids(&id_s)
     }
 
     pub async fn add_tag_to_connection(
@@ -189,6 +380,33 @@ impl<TCtx: Send + Sync + Default + 'static> SignalrConnectionsList<TCtx> {
         }
     }
 
+    pub async fn add_tag_value_to_connection(
+        &self,
+        ctx: &MySignalrConnection<TCtx>,
+        key: &str,
+        value: &str,
+    ) {
+        let mut write_access = self.sockets.write().await;
+
+        if write_access
+            .sockets_by_connection_token
+            .contains_key(ctx.get_list_index())
+        {
+            write_access
+                .tags
+                .add_tag_value(ctx.get_list_index(), key, value);
+        }
+    }
+
+    pub async fn get_connection_tag_values(
+        &self,
+        ctx: &MySignalrConnection<TCtx>,
+        key: &str,
+    ) -> Option<Vec<String>> {
+        let read_access = self.sockets.read().await;
+        read_access.tags.get_tag_values(ctx.get_list_index(), key)
+    }
+
     pub async fn remove_tag_from_connection(
         &self,
         ctx: Arc<MySignalrConnection<TCtx>>,
@@ -252,4 +470,85 @@ impl<TCtx: Send + Sync + Default + 'static> SignalrConnectionsList<TCtx> {
 
         None
     }
+
+    pub async fn get_connections_by_query(
+        &self,
+        query: &TagQuery,
+    ) -> Option<Vec<Arc<MySignalrConnection<TCtx>>>> {
+        let read_access = self.sockets.read().await;
+        let id_s = read_access
+            .tags
+            .query(query, read_access.sockets_by_connection_token.keys())?;
+        read_access.get_connections_by_ids(&id_s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::SignalrConnectionsList;
+    use crate::{MySignalrConnection, SignalrConnectionSettings, SignalrIdentity};
+
+    fn create_connection(connection_id: &str, user_id: &str) -> Arc<MySignalrConnection<()>> {
+        Arc::new(MySignalrConnection::new(
+            connection_id.to_string(),
+            None,
+            None,
+            SignalrConnectionSettings {
+                negotiation_version: 0,
+                stateful_reconnect: None,
+                outbound_queue: None,
+            },
+            Some(SignalrIdentity::new(user_id.to_string())),
+            (),
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_user_index() {
+        let list = SignalrConnectionsList::new();
+
+        assert!(
+            list.add_signalr_connection(create_connection("c1", "user-1"))
+                .await
+        );
+        assert!(
+            !list
+                .add_signalr_connection(create_connection("c2", "user-1"))
+                .await
+        );
+        assert!(
+            list.add_signalr_connection(create_connection("c3", "user-2"))
+                .await
+        );
+
+        assert_eq!(list.get_user_connections("user-1").await.unwrap().len(), 2);
+        assert!(list.is_user_connected("user-2").await);
+
+        list.remove("c1").await.unwrap();
+        assert_eq!(list.get_user_connections("user-1").await.unwrap().len(), 1);
+
+        list.remove("c2").await.unwrap();
+        assert!(!list.is_user_connected("user-1").await);
+        assert!(list.get_user_connections("user-1").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_users_connections_are_deduplicated() {
+        let list = SignalrConnectionsList::new();
+
+        list.add_signalr_connection(create_connection("c1", "user-1"))
+            .await;
+        list.add_signalr_connection(create_connection("c2", "user-2"))
+            .await;
+
+        let connections = list
+            .get_users_connections(&["user-1", "user-1", "user-2"])
+            .await
+            .unwrap();
+        assert_eq!(connections.len(), 2);
+
+        assert!(list.get_users_connections(&["user-3"]).await.is_none());
+    }
 }
@@ -0,0 +1,25 @@
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Default)]
+pub struct SignalrIdentity {
+    pub user_id: Option<String>,
+    pub claims: HashMap<String, String>,
+}
+
+impl SignalrIdentity {
+    pub fn new(user_id: String) -> Self {
+        Self {
+            user_id: Some(user_id),
+            claims: HashMap::new(),
+        }
+    }
+
+    pub fn anonymous() -> Self {
+        Self::default()
+    }
+
+    pub fn with_claim(mut self, key: String, value: String) -> Self {
+        self.claims.insert(key, value);
+        self
+    }
+}
@@ -0,0 +1,135 @@
+use std::sync::OnceLock;
+
+use crate::{messages::HubProtocol, SignalRParam, SignalrContractSerializer};
+
+pub struct SignalrInvocationFrame {
+    action_name: String,
+    payload: Vec<Vec<u8>>,
+    msgpack_payload: Option<Vec<Vec<u8>>>,
+    json: Vec<u8>,
+    msgpack: OnceLock<Result<Vec<u8>, String>>,
+}
+
+impl SignalrInvocationFrame {
+    pub fn new(action_name: &str, payload: Vec<Vec<u8>>) -> Self {
+        Self::new_with_msgpack(action_name, payload, None)
+    }
+
+    pub fn from_contract(action_name: &str, contract: impl SignalrContractSerializer) -> Self {
+        let msgpack_payload = contract.serialize_msgpack();
+        Self::new_with_msgpack(action_name, contract.serialize(), msgpack_payload)
+    }
+
+    pub fn new_with_msgpack(
+        action_name: &str,
+        payload: Vec<Vec<u8>>,
+        msgpack_payload: Option<Vec<Vec<u8>>>,
+    ) -> Self {
+        let json = crate::messages::generate_invocation_payload(
+            None,
+            action_name,
+            &[SignalRParam::Raw(payload.as_slice())],
+        );
+
+        Self {
+            action_name: action_name.to_string(),
+            payload,
+            msgpack_payload,
+            json,
+            msgpack: OnceLock::new(),
+        }
+    }
+
+    pub fn get(&self, hub_protocol: HubProtocol) -> Result<&[u8], &str> {
+        match hub_protocol {
+            HubProtocol::Json => Ok(self.json.as_slice()),
+            HubProtocol::MessagePack => self
+                .msgpack
+                .get_or_init(|| {
+                    let param = match self.msgpack_payload.as_ref() {
+                        Some(msgpack_payload) => SignalRParam::MessagePack(msgpack_payload),
+                        None => SignalRParam::Raw(self.payload.as_slice()),
+                    };
+
+                    crate::messages::generate_msgpack_invocation_payload(
+                        None,
+                        self.action_name.as_str(),
+                        &[param],
+                    )
+                })
+                .as_ref()
+                .map(|payload| payload.as_slice())
+                .map_err(|err| err.as_str()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SignalrInvocationFrame;
+    use crate::{
+        messages::{
+            msgpack::{msgpack_message_to_json, split_binary_frames, write_int},
+            HubProtocol,
+        },
+        SignalrContractSerializer,
+    };
+
+    fn decode_msgpack_frame(frame: &SignalrInvocationFrame) -> String {
+        let frames = split_binary_frames(frame.get(HubProtocol::MessagePack).unwrap()).unwrap();
+        assert_eq!(1, frames.len());
+        msgpack_message_to_json(frames[0]).unwrap()
+    }
+
+    #[test]
+    fn test_frame_is_built_per_protocol() {
+        let frame = SignalrInvocationFrame::new("update", vec!["{\"a\":1}".as_bytes().to_vec()]);
+
+        assert_eq!(
+            "{\"type\":1,\"target\":\"update\",\"arguments\":[{\"a\":1}]}\u{1e}".as_bytes(),
+            frame.get(HubProtocol::Json).unwrap()
+        );
+
+        assert_eq!(
+            "{\"type\":1,\"target\":\"update\",\"arguments\":[{\"a\":1}]}",
+            decode_msgpack_frame(&frame)
+        );
+    }
+
+    struct OverriddenContract;
+
+    impl SignalrContractSerializer for OverriddenContract {
+        fn serialize(self) -> Vec<Vec<u8>> {
+            vec!["\"json\"".as_bytes().to_vec()]
+        }
+
+        fn serialize_msgpack(&self) -> Option<Vec<Vec<u8>>> {
+            let mut payload = Vec::new();
+            write_int(42, &mut payload);
+            Some(vec![payload])
+        }
+    }
+
+    #[test]
+    fn test_frame_uses_msgpack_serializer() {
+        let frame = SignalrInvocationFrame::from_contract("update", OverriddenContract);
+
+        assert_eq!(
+            "{\"type\":1,\"target\":\"update\",\"arguments\":[\"json\"]}\u{1e}".as_bytes(),
+            frame.get(HubProtocol::Json).unwrap()
+        );
+
+        assert_eq!(
+            "{\"type\":1,\"target\":\"update\",\"arguments\":[42]}",
+            decode_msgpack_frame(&frame)
+        );
+    }
+
+    #[test]
+    fn test_invalid_json_payload_fails_msgpack_frame() {
+        let frame = SignalrInvocationFrame::new("update", vec!["{\"a\":".as_bytes().to_vec()]);
+
+        assert!(frame.get(HubProtocol::Json).is_ok());
+        assert!(frame.get(HubProtocol::MessagePack).is_err());
+    }
+}
@@ -0,0 +1,8 @@
+#[derive(Debug)]
+pub enum SignalrInvokeError {
+    Disconnected,
+    Timeout,
+    ClientError(String),
+    SerializeError(String),
+    DeserializeError(String),
+}
@@ -1,41 +1,54 @@
 use std::sync::Arc;
 
-use crate::{MySignalrConnection, SignalRParam, SignalrConnectionsList};
+use futures::StreamExt;
+
+use crate::{MySignalrConnection, SignalrConnectionsList, SignalrInvocationFrame, TagQuery};
+
+pub const DEFAULT_MAX_CONCURRENT_SENDS: usize = 64;
 
 pub trait SignalrContractSerializer {
     fn serialize(self) -> Vec<Vec<u8>>;
+
+    fn serialize_msgpack(&self) -> Option<Vec<Vec<u8>>> {
+        None
+    }
+}
+
+impl SignalrContractSerializer for () {
+    fn serialize(self) -> Vec<Vec<u8>> {
+        vec![]
+    }
 }
 pub struct SignalrMessagePublisher<
     TContract: SignalrContractSerializer + Send + Sync + 'static,
-    TCtx: Default + Send + Sync + 'static,
+    TCtx: Send + Sync + 'static,
 > {
     signalr_list: Arc<SignalrConnectionsList<TCtx>>,
     itm: std::marker::PhantomData<TContract>,
     action_name: String,
+    max_concurrent_sends: usize,
 }
 
-impl<
-        TContract: SignalrContractSerializer + Send + Sync + 'static,
-        TCtx: Default + Send + Sync + 'static,
-    > SignalrMessagePublisher<TContract, TCtx>
+impl<TContract: SignalrContractSerializer + Send + Sync + 'static, TCtx: Send + Sync + 'static>
+    SignalrMessagePublisher<TContract, TCtx>
 {
     pub fn new(action_name: String, signalr_list: Arc<SignalrConnectionsList<TCtx>>) -> Self {
         Self {
             action_name,
             signalr_list,
             itm: std::marker::PhantomData,
+            max_concurrent_sends: DEFAULT_MAX_CONCURRENT_SENDS,
         }
     }
 
+    pub fn with_max_concurrent_sends(mut self, max_concurrent_sends: usize) -> Self {
+        self.max_concurrent_sends = max_concurrent_sends;
+        self
+    }
+
     pub async fn broadcast_to_all(&self, contract: TContract) {
         if let Some(connections) = self.signalr_list.get_all().await {
-            let payload = contract.serialize();
-
-            for connection in connections {
-                let params = SignalRParam::Raw(payload.as_slice());
-
-                connection.send(self.action_name.as_str(), &params).await;
-            }
+            self.publish(connections, &[], contract).await;
         }
     }
 
@@ -44,19 +57,37 @@ impl<
         connection: &MySignalrConnection<TCtx>,
         contract: TContract,
     ) {
-        let payload = contract.serialize();
-        let params = SignalRParam::Raw(payload.as_slice());
-        connection.send(self.action_name.as_str(), &params).await;
+        let frame = SignalrInvocationFrame::from_contract(self.action_name.as_str(), contract);
+        connection.send_frame(&frame).await;
+    }
+
+    pub async fn send_to_user(&self, user_id: &str, contract: TContract) {
+        if let Some(connections) = self.signalr_list.get_user_connections(user_id).await {
+            self.publish(connections, &[], contract).await;
+        }
+    }
+
+    pub async fn send_to_users(&self, user_ids: &[&str], contract: TContract) {
+        if let Some(connections) = self.signalr_list.get_users_connections(user_ids).await {
+            self.publish(connections, &[], contract).await;
+        }
+    }
+
+    pub async fn send_to_group(&self, group: &str, contract: TContract) {
+        if let Some(connections) = self.signalr_list.get_group_connections(group).await {
+            self.publish(connections, &[], contract).await;
+        }
+    }
+
+    pub async fn send_to_groups(&self, groups: &[&str], contract: TContract) {
+        if let Some(connections) = self.signalr_list.get_groups_connections(groups).await {
+            self.publish(connections, &[], contract).await;
+        }
     }
 
     pub async fn send_to_tagged_connections(&self, key: &str, contract: TContract) {
         if let Some(connections) = self.signalr_list.get_tagged_connections(key).await {
-            let payload = contract.serialize();
-
-            for connection in connections {
-                let params = SignalRParam::Raw(payload.as_slice());
-                connection.send(self.action_name.as_str(), &params).await;
-            }
+            self.publish(connections, &[], contract).await;
         }
     }
 
@@ -71,12 +102,128 @@ impl<
             .get_tagged_connections_with_value(key, value)
             .await
         {
-            let payload = contract.serialize();
+            self.publish(connections, &[], contract).await;
+        }
+    }
+
+    pub async fn send_to_query(&self, query: &TagQuery, contract: TContract) {
+        if let Some(connections) = self.signalr_list.get_connections_by_query(query).await {
+            self.publish(connections, &[], contract).await;
+        }
+    }
 
-            for connection in connections {
-                let params = SignalRParam::Raw(payload.as_slice());
-                connection.send(self.action_name.as_str(), &params).await;
-            }
+    pub async fn broadcast_to_all_except(
+        &self,
+        excluded_connections: &[&str],
+        contract: TContract,
+    ) {
+        if let Some(connections) = self.signalr_list.get_all().await {
+            self.publish(connections, excluded_connections, contract)
+                .await;
         }
     }
+
+    pub async fn broadcast_to_others(
+        &self,
+        connection: &MySignalrConnection<TCtx>,
+        contract: TContract,
+    ) {
+        self.broadcast_to_all_except(&[connection.connection_id.as_str()], contract)
+            .await;
+    }
+
+    pub async fn send_to_group_except(
+        &self,
+        group: &str,
+        excluded_connections: &[&str],
+        contract: TContract,
+    ) {
+        if let Some(connections) = self.signalr_list.get_group_connections(group).await {
+            self.publish(connections, excluded_connections, contract)
+                .await;
+        }
+    }
+
+    pub async fn send_to_others_in_group(
+        &self,
+        group: &str,
+        connection: &MySignalrConnection<TCtx>,
+        contract: TContract,
+    ) {
+        self.send_to_group_except(group, &[connection.connection_id.as_str()], contract)
+            .await;
+    }
+
+    pub async fn send_to_tagged_connections_except(
+        &self,
+        key: &str,
+        excluded_connections: &[&str],
+        contract: TContract,
+    ) {
+        if let Some(connections) = self.signalr_list.get_tagged_connections(key).await {
+            self.publish(connections, excluded_connections, contract)
+                .await;
+        }
+    }
+
+    pub async fn send_to_other_tagged_connections(
+        &self,
+        key: &str,
+        connection: &MySignalrConnection<TCtx>,
+        contract: TContract,
+    ) {
+        self.send_to_tagged_connections_except(key, &[connection.connection_id.as_str()], contract)
+            .await;
+    }
+
+    pub async fn send_to_tagged_connections_with_value_except(
+        &self,
+        key: &str,
+        value: &str,
+        excluded_connections: &[&str],
+        contract: TContract,
+    ) {
+        if let Some(connections) = self
+            .signalr_list
+            .get_tagged_connections_with_value(key, value)
+            .await
+        {
+            self.publish(connections, excluded_connections, contract)
+                .await;
+        }
+    }
+
+    pub async fn send_to_other_tagged_connections_with_value(
+        &self,
+        key: &str,
+        value: &str,
+        connection: &MySignalrConnection<TCtx>,
+        contract: TContract,
+    ) {
+        self.send_to_tagged_connections_with_value_except(
+            key,
+            value,
+            &[connection.connection_id.as_str()],
+            contract,
+        )
+        .await;
+    }
+
+    async fn publish(
+        &self,
+        connections: Vec<Arc<MySignalrConnection<TCtx>>>,
+        excluded_connections: &[&str],
+        contract: TContract,
+    ) {
+        let frame = SignalrInvocationFrame::from_contract(self.action_name.as_str(), contract);
+        let frame = &frame;
+
+        futures::stream::iter(connections.iter().filter(|connection| {
+            !excluded_connections.contains(&connection.connection_id.as_str())
+        }))
+        .for_each_concurrent(self.max_concurrent_sends, |connection| async move {
+            connection.send_frame(frame).await;
+        })
+        .await;
+    }
 }
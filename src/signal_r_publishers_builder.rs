@@ -1,19 +1,44 @@
 use std::sync::Arc;
 
-use crate::{SignalrConnectionsList, SignalrContractSerializer, SignalrMessagePublisher};
+use crate::{
+    SignalrConflatingPublisher, SignalrConnectionsList, SignalrContractSerializer,
+    SignalrMessagePublisher,
+};
 
-pub struct SignalRPublshersBuilder<TCtx: Send + Sync + Default + 'static> {
+pub struct SignalRPublshersBuilder<TCtx: Send + Sync + 'static> {
     signalr_list: Arc<SignalrConnectionsList<TCtx>>,
+    max_concurrent_sends: usize,
 }
 
-impl<TCtx: Send + Sync + Default + 'static> SignalRPublshersBuilder<TCtx> {
+impl<TCtx: Send + Sync + 'static> SignalRPublshersBuilder<TCtx> {
     pub fn new(signalr_list: Arc<SignalrConnectionsList<TCtx>>) -> Self {
-        Self { signalr_list }
+        Self {
+            signalr_list,
+            max_concurrent_sends: crate::DEFAULT_MAX_CONCURRENT_SENDS,
+        }
     }
+
+    /// Zero means no limit.
+    pub fn with_max_concurrent_sends(mut self, max_concurrent_sends: usize) -> Self {
+        self.max_concurrent_sends = max_concurrent_sends;
+        self
+    }
+
     pub fn get_publisher<TContract: SignalrContractSerializer + Send + Sync + 'static>(
         &self,
         action_name: String,
     ) -> SignalrMessagePublisher<TContract, TCtx> {
-        return SignalrMessagePublisher::new(action_name, self.signalr_list.clone());
+        return SignalrMessagePublisher::new(action_name, self.signalr_list.clone())
+            .with_max_concurrent_sends(self.max_concurrent_sends);
+    }
+
+    pub fn get_conflating_publisher<
+        TContract: SignalrContractSerializer + Send + Sync + 'static,
+    >(
+        &self,
+        action_name: String,
+    ) -> SignalrConflatingPublisher<TContract, TCtx> {
+        SignalrConflatingPublisher::new(action_name, self.signalr_list.clone())
+            .with_max_concurrent_sends(self.max_concurrent_sends)
     }
 }
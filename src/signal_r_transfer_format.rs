@@ -0,0 +1,14 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignalrTransferFormat {
+    Text,
+    Binary,
+}
+
+impl SignalrTransferFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Text => "Text",
+            Self::Binary => "Binary",
+        }
+    }
+}
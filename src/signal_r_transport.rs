@@ -0,0 +1,86 @@
+use crate::SignalrTransferFormat;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignalrTransport {
+    WebSocket,
+    ServerSentEvents,
+    LongPolling,
+}
+
+impl SignalrTransport {
+    pub const ALL: [SignalrTransport; 3] = [
+        SignalrTransport::WebSocket,
+        SignalrTransport::ServerSentEvents,
+        SignalrTransport::LongPolling,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::WebSocket => "WebSockets",
+            Self::ServerSentEvents => "ServerSentEvents",
+            Self::LongPolling => "LongPolling",
+        }
+    }
+
+    fn as_flag(&self) -> u8 {
+        match self {
+            Self::WebSocket => 1,
+            Self::ServerSentEvents => 2,
+            Self::LongPolling => 4,
+        }
+    }
+
+    pub fn get_supported_transfer_formats(&self) -> &'static [SignalrTransferFormat] {
+        match self {
+            Self::WebSocket => &[SignalrTransferFormat::Text, SignalrTransferFormat::Binary],
+            Self::ServerSentEvents => &[SignalrTransferFormat::Text],
+            Self::LongPolling => &[SignalrTransferFormat::Text, SignalrTransferFormat::Binary],
+        }
+    }
+
+    pub fn parse_hint(value: &str) -> Vec<SignalrTransport> {
+        if let Ok(flags) = value.parse::<u8>() {
+            return Self::ALL
+                .into_iter()
+                .filter(|transport| flags & transport.as_flag() > 0)
+                .collect();
+        }
+
+        value
+            .split(',')
+            .filter_map(|name| {
+                Self::ALL
+                    .into_iter()
+                    .find(|transport| transport.as_str().eq_ignore_ascii_case(name.trim()))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SignalrTransport;
+
+    #[test]
+    fn test_parse_transport_names() {
+        let result = SignalrTransport::parse_hint("websockets,LongPolling");
+
+        assert_eq!(
+            result,
+            vec![SignalrTransport::WebSocket, SignalrTransport::LongPolling]
+        );
+    }
+
+    #[test]
+    fn test_parse_transport_flags() {
+        let result = SignalrTransport::parse_hint("3");
+
+        assert_eq!(
+            result,
+            vec![
+                SignalrTransport::WebSocket,
+                SignalrTransport::ServerSentEvents
+            ]
+        );
+    }
+}
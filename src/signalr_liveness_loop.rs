@@ -4,11 +4,12 @@ use rust_extensions::date_time::DateTimeAsMicroseconds;
 
 use crate::{MySignalrCallbacks, MySignalrConnection, SignalrConnectionsList};
 
-pub async fn start<TCtx: Send + Sync + Default + 'static>(
+pub async fn start<TCtx: Send + Sync + 'static>(
     connect_events: Arc<dyn MySignalrCallbacks<TCtx = TCtx> + Send + Sync + 'static>,
     sockets_list: Arc<SignalrConnectionsList<TCtx>>,
     my_socket_io_connection: Arc<MySignalrConnection<TCtx>>,
     ping_disconnect: Duration,
+    keep_alive_interval: Duration,
 ) {
     #[cfg(feature = "debug_ws")]
     println!(
@@ -17,9 +18,28 @@ pub async fn start<TCtx: Send + Sync + Default + 'static>(
     );
 
     while my_socket_io_connection.is_connected() {
-        tokio::time::sleep(Duration::from_secs(5)).await;
+        tokio::time::sleep(keep_alive_interval.min(Duration::from_secs(5))).await;
 
         let now = DateTimeAsMicroseconds::now();
+
+        if let Some(detached_moment) = my_socket_io_connection.get_detached_moment().await {
+            let grace_period = my_socket_io_connection
+                .stateful_reconnect
+                .map(|settings| settings.grace_period)
+                .unwrap_or_default();
+
+            if now.duration_since(detached_moment).as_positive_or_zero() > grace_period {
+                #[cfg(feature = "debug_ws")]
+                println!(
+                    "Signalr {} with connection token {:?} did not reconnect. Disconnecting",
+                    my_socket_io_connection.connection_id, my_socket_io_connection.connection_token
+                );
+                break;
+            }
+
+            continue;
+        }
+
         let last_incoming = my_socket_io_connection.get_last_incoming();
 
         if now.duration_since(last_incoming).as_positive_or_zero() > ping_disconnect {
@@ -30,7 +50,20 @@ pub async fn start<TCtx: Send + Sync + Default + 'static>(
             );
             break;
         }
+
+        my_socket_io_connection.send_ack_if_needed().await;
+
+        let last_outgoing = my_socket_io_connection.get_last_outgoing();
+
+        if now.duration_since(last_outgoing).as_positive_or_zero() >= keep_alive_interval {
+            my_socket_io_connection.send_ping_payload().await;
+        }
     }
 
-    crate::process_disconnect(&sockets_list, &my_socket_io_connection, &connect_events).await;
+    crate::process_disconnect(
+        &sockets_list,
+        my_socket_io_connection.get_list_index(),
+        &connect_events,
+    )
+    .await;
 }
@@ -1,5 +1,7 @@
 use my_json::json_writer::JsonObjectWriter;
 
+use crate::messages::msgpack;
+
 pub enum SignalRParam<'s> {
     JsonObject(&'s JsonObjectWriter),
     String(&'s str),
@@ -7,5 +9,188 @@ pub enum SignalRParam<'s> {
     Float(f64),
     Boolean(bool),
     Raw(&'s [Vec<u8>]),
+    MessagePack(&'s [Vec<u8>]),
+    Null,
     None,
 }
+
+impl<'s> SignalRParam<'s> {
+    pub fn get_arguments_amount(&self) -> usize {
+        match self {
+            SignalRParam::Raw(value) => value.len(),
+            SignalRParam::MessagePack(value) => value.len(),
+            SignalRParam::None => 0,
+            _ => 1,
+        }
+    }
+
+    pub fn write_all_into(parameters: &[SignalRParam<'s>], result: &mut Vec<u8>) {
+        let mut has_arguments = false;
+
+        for parameter in parameters {
+            if parameter.get_arguments_amount() == 0 {
+                continue;
+            }
+
+            if has_arguments {
+                result.push(b',');
+            }
+
+            parameter.write_into(result);
+            has_arguments = true;
+        }
+    }
+
+    pub fn write_all_msgpack_into(
+        parameters: &[SignalRParam<'s>],
+        result: &mut Vec<u8>,
+    ) -> Result<(), String> {
+        let amount = parameters
+            .iter()
+            .map(|parameter| parameter.get_arguments_amount())
+            .sum();
+
+        msgpack::write_array_len(amount, result);
+
+        for parameter in parameters {
+            parameter.write_msgpack_arguments_into(result)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn write_into(&self, result: &mut Vec<u8>) {
+        match self {
+            SignalRParam::JsonObject(json_writer) => {
+                json_writer.build_into(result);
+            }
+            SignalRParam::String(value) => {
+                let json_string = my_json::EscapedJsonString::new(value);
+                result.push(b'"');
+                result.extend_from_slice(json_string.as_str().as_bytes());
+                result.push(b'"');
+            }
+            SignalRParam::Number(number) => {
+                result.extend_from_slice(number.to_string().as_bytes());
+            }
+            SignalRParam::Float(value) => {
+                result.extend_from_slice(value.to_string().as_bytes());
+            }
+            SignalRParam::Boolean(value) => {
+                if *value {
+                    result.extend_from_slice("true".as_bytes());
+                } else {
+                    result.extend_from_slice("false".as_bytes());
+                }
+            }
+            SignalRParam::Raw(value) => {
+                for (index, item) in value.iter().enumerate() {
+                    if index > 0 {
+                        result.push(b',');
+                    }
+                    result.extend_from_slice(item.as_slice());
+                }
+            }
+            SignalRParam::MessagePack(value) => {
+                for (index, item) in value.iter().enumerate() {
+                    if index > 0 {
+                        result.push(b',');
+                    }
+                    if msgpack::msgpack_to_json(item.as_slice(), result).is_err() {
+                        result.extend_from_slice("null".as_bytes());
+                    }
+                }
+            }
+            SignalRParam::Null => {
+                result.extend_from_slice("null".as_bytes());
+            }
+            SignalRParam::None => {}
+        }
+    }
+
+    pub fn write_msgpack_into(&self, result: &mut Vec<u8>) -> Result<(), String> {
+        msgpack::write_array_len(self.get_arguments_amount(), result);
+        self.write_msgpack_arguments_into(result)
+    }
+
+    fn write_msgpack_arguments_into(&self, result: &mut Vec<u8>) -> Result<(), String> {
+        match self {
+            SignalRParam::JsonObject(json_writer) => {
+                let mut json = Vec::new();
+                json_writer.build_into(&mut json);
+                crate::messages::write_json_as_msgpack(json.as_slice(), result)?;
+            }
+            SignalRParam::String(value) => {
+                msgpack::write_str(value, result);
+            }
+            SignalRParam::Number(number) => {
+                msgpack::write_int(*number, result);
+            }
+            SignalRParam::Float(value) => {
+                msgpack::write_f64(*value, result);
+            }
+            SignalRParam::Boolean(value) => {
+                msgpack::write_bool(*value, result);
+            }
+            SignalRParam::Raw(value) => {
+                for item in value.iter() {
+                    crate::messages::write_json_as_msgpack(item.as_slice(), result)?;
+                }
+            }
+            SignalRParam::MessagePack(value) => {
+                for item in value.iter() {
+                    result.extend_from_slice(item.as_slice());
+                }
+            }
+            SignalRParam::Null => {
+                msgpack::write_nil(result);
+            }
+            SignalRParam::None => {}
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SignalRParam;
+
+    #[test]
+    fn test_multiple_arguments() {
+        let raw = vec!["{\"a\":1}".as_bytes().to_vec()];
+
+        let mut result = Vec::new();
+        SignalRParam::write_all_into(
+            &[
+                SignalRParam::String("a"),
+                SignalRParam::None,
+                SignalRParam::Number(1),
+                SignalRParam::Null,
+                SignalRParam::Raw(raw.as_slice()),
+            ],
+            &mut result,
+        );
+
+        assert_eq!(
+            "\"a\",1,null,{\"a\":1}",
+            std::str::from_utf8(result.as_slice()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_multiple_msgpack_arguments() {
+        let mut result = Vec::new();
+        SignalRParam::write_all_msgpack_into(
+            &[
+                SignalRParam::Boolean(true),
+                SignalRParam::None,
+                SignalRParam::Null,
+            ],
+            &mut result,
+        )
+        .unwrap();
+
+        assert_eq!(vec![0x92, 0xc3, 0xc0], result);
+    }
+}
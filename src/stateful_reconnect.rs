@@ -0,0 +1,171 @@
+use std::{collections::VecDeque, time::Duration};
+
+#[derive(Debug, Clone, Copy)]
+pub struct StatefulReconnectSettings {
+    pub buffer_size: usize,
+    pub grace_period: Duration,
+}
+
+pub struct StatefulReconnectBuffer {
+    buffer_size: usize,
+    messages: VecDeque<(u64, Vec<u8>)>,
+    buffered_size: usize,
+    next_sequence_id: u64,
+    lost_sequence_id: u64,
+    next_incoming_sequence_id: u64,
+    latest_received_sequence_id: u64,
+    latest_acked_received_sequence_id: u64,
+}
+
+impl StatefulReconnectBuffer {
+    pub fn new(buffer_size: usize) -> Self {
+        Self {
+            buffer_size,
+            messages: VecDeque::new(),
+            buffered_size: 0,
+            next_sequence_id: 1,
+            lost_sequence_id: 0,
+            next_incoming_sequence_id: 1,
+            latest_received_sequence_id: 0,
+            latest_acked_received_sequence_id: 0,
+        }
+    }
+
+    pub fn push(&mut self, payload: Vec<u8>) {
+        self.buffered_size += payload.len();
+        self.messages.push_back((self.next_sequence_id, payload));
+        self.next_sequence_id += 1;
+
+        while self.buffered_size > self.buffer_size {
+            match self.messages.pop_front() {
+                Some((sequence_id, payload)) => {
+                    self.buffered_size -= payload.len();
+                    self.lost_sequence_id = sequence_id;
+                }
+                None => break,
+            }
+        }
+    }
+
+    pub fn ack(&mut self, sequence_id: u64) {
+        while let Some((first_sequence_id, _)) = self.messages.front() {
+            if *first_sequence_id > sequence_id {
+                break;
+            }
+
+            let (_, payload) = self.messages.pop_front().unwrap();
+            self.buffered_size -= payload.len();
+        }
+
+        if sequence_id >= self.lost_sequence_id {
+            self.lost_sequence_id = 0;
+        }
+    }
+
+    pub fn get_unacknowledged(&self) -> Result<(u64, Vec<Vec<u8>>), String> {
+        if self.lost_sequence_id > 0 {
+            return Err(format!(
+                "Stateful reconnect buffer has overflowed. Message with sequence id {} is lost",
+                self.lost_sequence_id
+            ));
+        }
+
+        let first_sequence_id = match self.messages.front() {
+            Some((sequence_id, _)) => *sequence_id,
+            None => self.next_sequence_id,
+        };
+
+        let payloads = self
+            .messages
+            .iter()
+            .map(|(_, payload)| payload.clone())
+            .collect();
+
+        Ok((first_sequence_id, payloads))
+    }
+
+    pub fn set_incoming_sequence(&mut self, sequence_id: u64) -> bool {
+        if sequence_id > self.latest_received_sequence_id + 1 {
+            return false;
+        }
+
+        self.next_incoming_sequence_id = sequence_id;
+        true
+    }
+
+    pub fn accept_incoming(&mut self) -> bool {
+        let sequence_id = self.next_incoming_sequence_id;
+        self.next_incoming_sequence_id += 1;
+
+        if sequence_id <= self.latest_received_sequence_id {
+            return false;
+        }
+
+        self.latest_received_sequence_id = sequence_id;
+        true
+    }
+
+    pub fn get_ack_to_send(&mut self) -> Option<u64> {
+        if self.latest_received_sequence_id == self.latest_acked_received_sequence_id {
+            return None;
+        }
+
+        self.latest_acked_received_sequence_id = self.latest_received_sequence_id;
+        Some(self.latest_received_sequence_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StatefulReconnectBuffer;
+
+    #[test]
+    fn test_replay_unacknowledged() {
+        let mut buffer = StatefulReconnectBuffer::new(1024);
+
+        buffer.push(vec![1]);
+        buffer.push(vec![2]);
+        buffer.push(vec![3]);
+
+        buffer.ack(1);
+
+        let (first_sequence_id, payloads) = buffer.get_unacknowledged().unwrap();
+
+        assert_eq!(2, first_sequence_id);
+        assert_eq!(vec![vec![2], vec![3]], payloads);
+    }
+
+    #[test]
+    fn test_overflow_is_reported_until_acked() {
+        let mut buffer = StatefulReconnectBuffer::new(2);
+
+        buffer.push(vec![1]);
+        buffer.push(vec![2]);
+        buffer.push(vec![3]);
+
+        assert!(buffer.get_unacknowledged().is_err());
+
+        buffer.ack(2);
+
+        let (first_sequence_id, payloads) = buffer.get_unacknowledged().unwrap();
+
+        assert_eq!(3, first_sequence_id);
+        assert_eq!(vec![vec![3]], payloads);
+    }
+
+    #[test]
+    fn test_duplicates_are_skipped_after_sequence() {
+        let mut buffer = StatefulReconnectBuffer::new(1024);
+
+        assert!(buffer.accept_incoming());
+        assert!(buffer.accept_incoming());
+        assert_eq!(Some(2), buffer.get_ack_to_send());
+        assert_eq!(None, buffer.get_ack_to_send());
+
+        assert!(buffer.set_incoming_sequence(2));
+        assert!(!buffer.accept_incoming());
+        assert!(buffer.accept_incoming());
+
+        assert!(!buffer.set_incoming_sequence(5));
+    }
+}
@@ -0,0 +1,50 @@
+#[derive(Debug, Clone)]
+pub enum TagQuery {
+    Eq(String, String),
+    In(String, Vec<String>),
+    HasKey(String),
+    And(Vec<TagQuery>),
+    Or(Vec<TagQuery>),
+    Not(Box<TagQuery>),
+}
+
+impl TagQuery {
+    pub fn eq(key: &str, value: &str) -> Self {
+        Self::Eq(key.to_string(), value.to_string())
+    }
+
+    pub fn is_in(key: &str, values: &[&str]) -> Self {
+        Self::In(
+            key.to_string(),
+            values.iter().map(|value| value.to_string()).collect(),
+        )
+    }
+
+    pub fn has_key(key: &str) -> Self {
+        Self::HasKey(key.to_string())
+    }
+
+    pub fn and(self, other: TagQuery) -> Self {
+        match self {
+            Self::And(mut items) => {
+                items.push(other);
+                Self::And(items)
+            }
+            _ => Self::And(vec![self, other]),
+        }
+    }
+
+    pub fn or(self, other: TagQuery) -> Self {
+        match self {
+            Self::Or(mut items) => {
+                items.push(other);
+                Self::Or(items)
+            }
+            _ => Self::Or(vec![self, other]),
+        }
+    }
+
+    pub fn negate(self) -> Self {
+        Self::Not(Box::new(self))
+    }
+}
@@ -2,9 +2,11 @@ use std::collections::HashMap;
 
 use rust_extensions::lazy::LazyVec;
 
+use crate::TagQuery;
+
 pub struct Tags {
     pub tags_to_connection: HashMap<String, HashMap<String, HashMap<String, ()>>>,
-    pub connection_tags: HashMap<String, HashMap<String, String>>,
+    pub connection_tags: HashMap<String, HashMap<String, HashMap<String, ()>>>,
 }
 
 impl Tags {
@@ -16,25 +18,37 @@ impl Tags {
     }
 
     pub fn add_tag(&mut self, connection_id: &str, key: &str, value: &str) {
+        let removed_values = self
+            .connection_tags
+            .get_mut(connection_id)
+            .and_then(|tags| tags.remove(key));
+
+        if let Some(removed_values) = removed_values {
+            for removed_value in removed_values.keys() {
+                self.remove_from_index(connection_id, key, removed_value);
+            }
+        }
+
+        self.add_tag_value(connection_id, key, value);
+    }
+
+    pub fn add_tag_value(&mut self, connection_id: &str, key: &str, value: &str) {
         if !self.connection_tags.contains_key(connection_id) {
             self.connection_tags
                 .insert(connection_id.to_string(), HashMap::new());
         }
 
-        let removed_tag = self
-            .connection_tags
-            .get_mut(connection_id)
-            .unwrap()
-            .insert(key.to_string(), value.to_string());
+        let connection_tags = self.connection_tags.get_mut(connection_id).unwrap();
 
-        if let Some(removed_tag) = removed_tag {
-            if let Some(keys) = self.tags_to_connection.get_mut(key) {
-                if let Some(tags) = keys.get_mut(&removed_tag) {
-                    tags.remove(connection_id);
-                }
-            }
+        if !connection_tags.contains_key(key) {
+            connection_tags.insert(key.to_string(), HashMap::new());
         }
 
+        connection_tags
+            .get_mut(key)
+            .unwrap()
+            .insert(value.to_string(), ());
+
         // Fill Tags to conection
 
         if !self.tags_to_connection.contains_key(key) {
@@ -58,50 +72,64 @@ impl Tags {
         // Removing first part
 
         if let Some(tags_of_connections) = self.connection_tags.get_mut(connection_id) {
-            tags_of_connections.remove(key);
+            let remove_key = if let Some(values) = tags_of_connections.get_mut(key) {
+                values.remove(value);
+                values.is_empty()
+            } else {
+                false
+            };
+
+            if remove_key {
+                tags_of_connections.remove(key);
+            }
         }
 
         // Removing second part
 
-        if let Some(tags) = self.tags_to_connection.get_mut(key) {
-            let connections_remains = if let Some(connections) = tags.get_mut(value) {
+        self.remove_from_index(connection_id, key, value);
+    }
+
+    pub fn remove_connection(&mut self, connection_id: &str) {
+        if let Some(tags) = self.connection_tags.remove(connection_id) {
+            for (key, values) in tags {
+                for value in values.keys() {
+                    self.remove_from_index(connection_id, &key, value);
+                }
+            }
+        };
+    }
+
+    fn remove_from_index(&mut self, connection_id: &str, key: &str, value: &str) {
+        let remove_tag = if let Some(values) = self.tags_to_connection.get_mut(key) {
+            let remove = if let Some(connections) = values.get_mut(value) {
                 connections.remove(connection_id);
-                connections.len()
+                connections.is_empty()
             } else {
-                0
+                false
             };
 
-            if connections_remains == 0 {
-                tags.remove(value);
+            if remove {
+                values.remove(value);
             }
+
+            values.is_empty()
+        } else {
+            false
+        };
+
+        if remove_tag {
+            self.tags_to_connection.remove(key);
         }
     }
 
-    pub fn remove_connection(&mut self, connection_id: &str) {
-        if let Some(tags) = self.connection_tags.remove(connection_id) {
-            for (key, value) in tags {
-                let remove_tag = if let Some(values) = self.tags_to_connection.get_mut(&key) {
-                    let remove = if let Some(connections) = values.get_mut(&value) {
-                        connections.remove(connection_id);
-                        connections.is_empty()
-                    } else {
-                        false
-                    };
-
-                    if remove {
-                        values.remove(&value);
-                    }
+    pub fn get_tag_values(&self, connection_id: &str, key: &str) -> Option<Vec<String>> {
+        let values = self.connection_tags.get(connection_id)?.get(key)?;
 
-                    values.is_empty()
-                } else {
-                    false
-                };
+        if values.is_empty() {
+            return None;
+        }
 
-                if remove_tag {
-                    self.tags_to_connection.remove(&key);
-                }
-            }
-        };
+        Some(values.keys().cloned().collect())
     }
 
     pub fn get_tagged_connections_with_value(&self, key: &str, value: &str) -> Option<Vec<String>> {
@@ -126,18 +154,142 @@ impl Tags {
 
     pub fn get_tagged_connections(&self, key: &str) -> Option<Vec<String>> {
         if let Some(tags) = self.tags_to_connection.get(key) {
-            let mut result = LazyVec::new();
+            let mut connections = HashMap::new();
             for tags in tags.values() {
                 for connection_id in tags.keys() {
-                    result.add(connection_id.to_string());
+                    connections.insert(connection_id.as_str(), ());
                 }
             }
 
+            let mut result = LazyVec::new();
+
+            for connection_id in connections.into_keys() {
+                result.add(connection_id.to_string());
+            }
+
             return result.get_result();
         }
 
         None
     }
+
+    pub fn matches(&self, connection_id: &str, query: &TagQuery) -> bool {
+        match query {
+            TagQuery::Eq(key, value) => self.has_value(connection_id, key, value),
+            TagQuery::In(key, values) => values
+                .iter()
+                .any(|value| self.has_value(connection_id, key, value)),
+            TagQuery::HasKey(key) => match self.connection_tags.get(connection_id) {
+                Some(tags) => tags.contains_key(key),
+                None => false,
+            },
+            TagQuery::And(items) => items.iter().all(|item| self.matches(connection_id, item)),
+            TagQuery::Or(items) => items.iter().any(|item| self.matches(connection_id, item)),
+            TagQuery::Not(item) => !self.matches(connection_id, item),
+        }
+    }
+
+    pub fn query<'s>(
+        &'s self,
+        query: &TagQuery,
+        all_connections: impl Iterator<Item = &'s String>,
+    ) -> Option<Vec<String>> {
+        let mut result = LazyVec::new();
+
+        match self.get_candidates(query) {
+            Some(candidates) => {
+                for connection_id in candidates.into_keys() {
+                    if self.matches(connection_id, query) {
+                        result.add(connection_id.to_string());
+                    }
+                }
+            }
+            None => {
+                for connection_id in all_connections {
+                    if self.matches(connection_id, query) {
+                        result.add(connection_id.to_string());
+                    }
+                }
+            }
+        }
+
+        result.get_result()
+    }
+
+    fn has_value(&self, connection_id: &str, key: &str, value: &str) -> bool {
+        match self.connection_tags.get(connection_id) {
+            Some(tags) => match tags.get(key) {
+                Some(values) => values.contains_key(value),
+                None => false,
+            },
+            None => false,
+        }
+    }
+
+    // Narrows down the connections to check using the index. None means the query can not be
+    // answered from the index (e.g. NOT) and every connection has to be checked.
+    fn get_candidates<'s>(&'s self, query: &TagQuery) -> Option<HashMap<&'s str, ()>> {
+        match query {
+            TagQuery::Eq(key, value) => {
+                let mut result = HashMap::new();
+                self.fill_candidates(key, Some(value), &mut result);
+                Some(result)
+            }
+            TagQuery::In(key, values) => {
+                let mut result = HashMap::new();
+                for value in values {
+                    self.fill_candidates(key, Some(value), &mut result);
+                }
+                Some(result)
+            }
+            TagQuery::HasKey(key) => {
+                let mut result = HashMap::new();
+                self.fill_candidates(key, None, &mut result);
+                Some(result)
+            }
+            TagQuery::And(items) => items
+                .iter()
+                .filter_map(|item| self.get_candidates(item))
+                .min_by_key(|candidates| candidates.len()),
+            TagQuery::Or(items) => {
+                let mut result = HashMap::new();
+                for item in items {
+                    result.extend(self.get_candidates(item)?);
+                }
+                Some(result)
+            }
+            TagQuery::Not(_) => None,
+        }
+    }
+
+    fn fill_candidates<'s>(
+        &'s self,
+        key: &str,
+        value: Option<&str>,
+        result: &mut HashMap<&'s str, ()>,
+    ) {
+        let values = match self.tags_to_connection.get(key) {
+            Some(values) => values,
+            None => return,
+        };
+
+        match value {
+            Some(value) => {
+                if let Some(connections) = values.get(value) {
+                    for connection_id in connections.keys() {
+                        result.insert(connection_id.as_str(), ());
+                    }
+                }
+            }
+            None => {
+                for connections in values.values() {
+                    for connection_id in connections.keys() {
+                        result.insert(connection_id.as_str(), ());
+                    }
+                }
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -321,4 +473,88 @@ mod tests {
         println!("{:?}", connections_by_asset);
         assert_eq!(connections_by_asset.len(), 3);
     }
+
+    #[test]
+    fn test_multi_valued_tags() {
+        let mut tags = Tags::new();
+
+        tags.add_tag_value("c1", "asset", "BTCUSD");
+        tags.add_tag_value("c1", "asset", "ETHUSD");
+        tags.add_tag_value("c2", "asset", "ETHUSD");
+
+        assert_eq!(tags.get_tagged_connections("asset").unwrap().len(), 2);
+
+        assert_eq!(
+            tags.get_tagged_connections_with_value("asset", "ETHUSD")
+                .unwrap()
+                .len(),
+            2
+        );
+
+        tags.remove_tag("c1", "asset", "ETHUSD");
+
+        assert_eq!(
+            tags.get_tag_values("c1", "asset").unwrap(),
+            vec!["BTCUSD".to_string()]
+        );
+
+        tags.add_tag("c2", "asset", "XRPUSD");
+
+        assert_eq!(
+            tags.get_tag_values("c2", "asset").unwrap(),
+            vec!["XRPUSD".to_string()]
+        );
+
+        assert!(tags
+            .get_tagged_connections_with_value("asset", "ETHUSD")
+            .is_none());
+    }
+
+    #[test]
+    fn test_query() {
+        let mut tags = Tags::new();
+
+        tags.add_tag_value("c1", "asset", "BTCUSD");
+        tags.add_tag_value("c1", "asset", "ETHUSD");
+        tags.add_tag("c1", "tier", "pro");
+
+        tags.add_tag_value("c2", "asset", "ETHUSD");
+        tags.add_tag("c2", "tier", "basic");
+
+        tags.add_tag("c3", "tier", "pro");
+
+        let all_connections = ["c1".to_string(), "c2".to_string(), "c3".to_string()];
+
+        let mut result = tags
+            .query(
+                &TagQuery::eq("asset", "ETHUSD").and(TagQuery::eq("tier", "pro")),
+                all_connections.iter(),
+            )
+            .unwrap();
+        assert_eq!(result, vec!["c1".to_string()]);
+
+        result = tags
+            .query(
+                &TagQuery::eq("asset", "BTCUSD").or(TagQuery::eq("tier", "basic")),
+                all_connections.iter(),
+            )
+            .unwrap();
+        result.sort();
+        assert_eq!(result, vec!["c1".to_string(), "c2".to_string()]);
+
+        result = tags
+            .query(&TagQuery::has_key("asset").negate(), all_connections.iter())
+            .unwrap();
+        assert_eq!(result, vec!["c3".to_string()]);
+
+        result = tags
+            .query(
+                &TagQuery::is_in("tier", &["pro", "basic"])
+                    .and(TagQuery::eq("asset", "BTCUSD").negate()),
+                all_connections.iter(),
+            )
+            .unwrap();
+        result.sort();
+        assert_eq!(result, vec!["c2".to_string(), "c3".to_string()]);
+    }
 }
@@ -1,4 +1,4 @@
-use std::{sync::Arc, time::Duration};
+use std::{collections::HashMap, net::SocketAddr, sync::Arc, time::Duration};
 
 use hyper_tungstenite::tungstenite::Message;
 use my_http_server::HttpFailResult;
@@ -8,18 +8,35 @@ use my_json::json_reader::JsonFirstLineReader;
 use my_telemetry::MyTelemetryContext;
 #[cfg(feature = "my-telemetry")]
 use my_telemetry::TelemetryEventTagsBuilder;
+use tokio::sync::Mutex;
 
 use crate::{
-    messages::SignalrMessage, MySignalrCallbacks, MySignalrConnection, SignalrConnectionsList,
+    messages::{
+        msgpack::{msgpack_message_to_json, split_binary_frames},
+        split_text_frames, HubProtocol, SignalrCancelInvocationMessage, SignalrCompletionMessage,
+        SignalrHandshakeRequest, SignalrMessage, SignalrSequenceMessage, SignalrStreamItemMessage,
+    },
+    MySignalrCallbacks, MySignalrConnection, OutboundQueueSettings, SignalrConnectionSettings,
+    SignalrConnectionsList, SignalrIdentity, SignalrTransferFormat,
 };
 
-pub struct WebSocketCallbacks<TCtx: Send + Sync + Default + 'static> {
+pub struct WebSocketCallbacks<TCtx: Send + Sync + 'static> {
     pub signalr_list: Arc<SignalrConnectionsList<TCtx>>,
     pub my_signal_r_callbacks: Arc<dyn MySignalrCallbacks<TCtx = TCtx> + Send + Sync + 'static>,
+    pub keep_alive_interval: Duration,
+    pub transfer_formats: Vec<SignalrTransferFormat>,
+    pub allow_skip_negotiation: bool,
+    pub outbound_queue: Option<OutboundQueueSettings>,
+    pub(crate) pending_web_sockets: Mutex<HashMap<i64, PendingWebSocket<TCtx>>>,
+}
+
+pub(crate) struct PendingWebSocket<TCtx> {
+    identity: Option<SignalrIdentity>,
+    ctx: Option<TCtx>,
 }
 
 #[async_trait::async_trait]
-impl<TCtx: Send + Sync + Default + 'static> my_http_server_web_sockets::MyWebSocketCallback
+impl<TCtx: Send + Sync + 'static> my_http_server_web_sockets::MyWebSocketCallback
     for WebSocketCallbacks<TCtx>
 {
     async fn connected(
@@ -30,48 +47,89 @@ impl<TCtx: Send + Sync + Default + 'static> my_http_server_web_sockets::MyWebSoc
         #[cfg(feature = "debug_ws")]
         println!("connected web_socket:{}", my_web_socket.id);
 
-        if let Some(query_string) = my_web_socket.get_query_string() {
-            let connection_token = query_string.get_optional("id");
-
-            if connection_token.is_none() {
-                my_web_socket
-                    .send_message(Message::Text("id query parameter is missing".to_string()))
-                    .await;
-                return Ok(());
-            }
-
-            let connection_token = connection_token.unwrap();
-
-            match self
-                .signalr_list
-                .assign_web_socket(connection_token.value, my_web_socket.clone())
-                .await
-            {
-                Some(signalr_connection) => {
-                    tokio::spawn(super::signalr_liveness_loop::start(
-                        self.my_signal_r_callbacks.clone(),
-                        self.signalr_list.clone(),
-                        signalr_connection,
-                        disconnect_timeout,
-                    ));
+        let (identity, signalr_ctx) = match self.take_pending_web_socket(my_web_socket.id).await {
+            Some(pending) => (pending.identity, pending.ctx),
+            None => (None, None),
+        };
+
+        let query_string = my_web_socket.get_query_string();
+        let connection_token = query_string
+            .as_ref()
+            .and_then(|query_string| query_string.get_optional("id"));
+
+        let signalr_connection = match connection_token {
+            Some(connection_token) => {
+                match self
+                    .signalr_list
+                    .assign_web_socket(connection_token.value, my_web_socket.clone())
+                    .await
+                {
+                    Some(signalr_connection) => signalr_connection,
+                    None => {
+                        my_web_socket
+                            .send_message(Message::Text(format!(
+                                "SignalR with connection_token {} is not found",
+                                connection_token.value,
+                            )))
+                            .await;
+
+                        return Ok(());
+                    }
                 }
-                None => {
+            }
+            None => {
+                if !self.allow_skip_negotiation {
                     my_web_socket
-                        .send_message(Message::Text(format!(
-                            "SignalR with connection_token {} is not found",
-                            connection_token.value,
-                        )))
+                        .send_message(Message::Text("id query parameter is missing".to_string()))
                         .await;
-
                     return Ok(());
                 }
-            };
+
+                let signalr_ctx = match signalr_ctx {
+                    Some(signalr_ctx) => signalr_ctx,
+                    None => {
+                        return Err(HttpFailResult::as_fatal_error(
+                            "SignalR ctx is not initialized".to_string(),
+                        ))
+                    }
+                };
+
+                crate::process_connect(
+                    &self.my_signal_r_callbacks,
+                    &self.signalr_list,
+                    Some(my_web_socket.clone()),
+                    SignalrConnectionSettings {
+                        negotiation_version: 0,
+                        stateful_reconnect: None,
+                        outbound_queue: self.outbound_queue,
+                    },
+                    identity,
+                    signalr_ctx,
+                )
+                .await?
+            }
+        };
+
+        if signalr_connection.try_start_outbound_writer() {
+            tokio::spawn(super::outbound_writer::start(signalr_connection.clone()));
+        }
+
+        if signalr_connection.try_start_liveness_loop() {
+            tokio::spawn(super::signalr_liveness_loop::start(
+                self.my_signal_r_callbacks.clone(),
+                self.signalr_list.clone(),
+                signalr_connection,
+                disconnect_timeout,
+                self.keep_alive_interval,
+            ));
         }
 
         Ok(())
     }
 
     async fn disconnected(&self, my_web_socket: Arc<MyWebSocket>) {
+        self.take_pending_web_socket(my_web_socket.id).await;
+
         #[cfg(feature = "debug_ws")]
         println!("disconnected web_socket:{}", my_web_socket.id);
         let find_result = self
@@ -80,9 +138,20 @@ impl<TCtx: Send + Sync + Default + 'static> my_http_server_web_sockets::MyWebSoc
             .await;
 
         if let Some(signalr_connection) = find_result {
+            if signalr_connection.stateful_reconnect.is_some() && signalr_connection.is_connected()
+            {
+                #[cfg(feature = "debug_ws")]
+                println!(
+                    "Signalr {} lost web_socket:{}. Waiting for reconnect",
+                    signalr_connection.connection_id, my_web_socket.id
+                );
+                self.signalr_list.detach_web_socket(my_web_socket.id).await;
+                return;
+            }
+
             crate::process_disconnect(
                 &self.signalr_list,
-                &signalr_connection,
+                signalr_connection.get_list_index(),
                 &self.my_signal_r_callbacks,
             )
             .await;
@@ -100,58 +169,212 @@ impl<TCtx: Send + Sync + Default + 'static> my_http_server_web_sockets::MyWebSoc
         if let Some(signalr_connection) = signal_r.as_ref() {
             signalr_connection.update_incoming_activity();
 
+            let mut result = Ok(());
+
             if let WebSocketMessage::String(value) = &message {
                 if signalr_connection.get_has_greeting() {
-                    let packet_type = get_payload_type(value);
+                    result = self
+                        .handle_text_payload(&my_web_socket.addr, signalr_connection, value)
+                        .await;
+                } else if let Some(handshake_end) =
+                    read_first_payload(signalr_connection, &self.transfer_formats, value.as_bytes())
+                        .await
+                {
+                    result = self
+                        .handle_text_payload(
+                            &my_web_socket.addr,
+                            signalr_connection,
+                            &value[handshake_end..],
+                        )
+                        .await;
+                }
+            } else if let WebSocketMessage::Binary(value) = &message {
+                if signalr_connection.get_has_greeting() {
+                    result = self
+                        .handle_binary_payload(&my_web_socket.addr, signalr_connection, value)
+                        .await;
+                } else if let Some(handshake_end) =
+                    read_first_payload(signalr_connection, &self.transfer_formats, value).await
+                {
+                    let remains = &value[handshake_end..];
+                    if !remains.is_empty() {
+                        result = self
+                            .handle_binary_payload(&my_web_socket.addr, signalr_connection, remains)
+                            .await;
+                    }
+                }
+            }
 
-                    if packet_type == "1" {
-                        #[cfg(feature = "my-telemetry")]
-                        let ctx = MyTelemetryContext::new();
+            if let Err(_err) = result {
+                #[cfg(feature = "debug_ws")]
+                println!(
+                    "Signalr {}, invalid message: {}",
+                    signalr_connection.connection_id, _err
+                );
+            }
+        }
+    }
+}
 
-                        #[cfg(feature = "my-telemetry")]
-                        let started = rust_extensions::date_time::DateTimeAsMicroseconds::now();
+impl<TCtx: Send + Sync + 'static> WebSocketCallbacks<TCtx> {
+    pub(crate) async fn add_pending_web_socket(
+        &self,
+        web_socket_id: i64,
+        identity: Option<SignalrIdentity>,
+        ctx: Option<TCtx>,
+    ) {
+        let mut write_access = self.pending_web_sockets.lock().await;
+        write_access.insert(web_socket_id, PendingWebSocket { identity, ctx });
+    }
 
-                        let message = SignalrMessage::parse(value);
+    pub(crate) async fn take_pending_web_socket(
+        &self,
+        web_socket_id: i64,
+    ) -> Option<PendingWebSocket<TCtx>> {
+        let mut write_access = self.pending_web_sockets.lock().await;
+        write_access.remove(&web_socket_id)
+    }
 
-                        #[cfg(feature = "my-telemetry")]
-                        let ctx_spawned = ctx.clone();
+    pub async fn handle_http_payload(
+        &self,
+        addr: &SocketAddr,
+        signalr_connection: &Arc<MySignalrConnection<TCtx>>,
+        payload: &[u8],
+    ) -> Result<(), String> {
+        signalr_connection.update_incoming_activity();
+
+        let payload = if signalr_connection.get_has_greeting() {
+            payload
+        } else {
+            match read_first_payload(signalr_connection, &self.transfer_formats, payload).await {
+                Some(handshake_end) => &payload[handshake_end..],
+                None => return Ok(()),
+            }
+        };
 
-                        let signal_r_callbacks = self.my_signal_r_callbacks.clone();
+        if payload.is_empty() {
+            return Ok(());
+        }
 
-                        let connection_spawned = signalr_connection.clone();
+        match signalr_connection.get_hub_protocol().await {
+            HubProtocol::Json => {
+                let payload = std::str::from_utf8(payload)
+                    .map_err(|_| "Text payload is not a valid utf8 string".to_string())?;
+                self.handle_text_payload(addr, signalr_connection, payload)
+                    .await
+            }
+            HubProtocol::MessagePack => {
+                self.handle_binary_payload(addr, signalr_connection, payload)
+                    .await
+            }
+        }
+    }
 
-                        let target = message.target.to_string();
+    async fn handle_text_payload(
+        &self,
+        addr: &SocketAddr,
+        signalr_connection: &Arc<MySignalrConnection<TCtx>>,
+        payload: &str,
+    ) -> Result<(), String> {
+        for message in split_text_frames(payload) {
+            self.handle_message(addr, signalr_connection, message)
+                .await?;
+        }
 
-                        let arguments = message.arguments.to_vec();
+        Ok(())
+    }
 
-                        let _result = tokio::spawn(async move {
-                            #[cfg(feature = "my-telemetry")]
-                            let mut signal_r_telemetry = crate::SignalRTelemetry::new(ctx_spawned);
-                            signal_r_callbacks
-                                .on(
-                                    connection_spawned,
-                                    message.headers,
-                                    target,
-                                    arguments,
-                                    #[cfg(feature = "my-telemetry")]
-                                    &mut signal_r_telemetry,
-                                )
-                                .await;
+    async fn handle_binary_payload(
+        &self,
+        addr: &SocketAddr,
+        signalr_connection: &Arc<MySignalrConnection<TCtx>>,
+        payload: &[u8],
+    ) -> Result<(), String> {
+        for frame in split_binary_frames(payload)? {
+            let payload = msgpack_message_to_json(frame)?;
+            self.handle_message(addr, signalr_connection, &payload)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    #[cfg_attr(not(feature = "my-telemetry"), allow(unused_variables))]
+    async fn handle_message(
+        &self,
+        addr: &SocketAddr,
+        signalr_connection: &Arc<MySignalrConnection<TCtx>>,
+        value: &str,
+    ) -> Result<(), String> {
+        let packet_type = get_payload_type(value)?;
+
+        if matches!(packet_type, "1" | "2" | "3" | "4" | "5")
+            && !signalr_connection.accept_incoming_message().await
+        {
+            return Ok(());
+        }
+
+        if packet_type == "1" {
+            let message = SignalrMessage::parse(value);
+
+            if message.stream_ids.is_empty() {
+                #[cfg(feature = "my-telemetry")]
+                let ctx = MyTelemetryContext::new();
+
+                #[cfg(feature = "my-telemetry")]
+                let started = rust_extensions::date_time::DateTimeAsMicroseconds::now();
+
+                #[cfg(feature = "my-telemetry")]
+                let ctx_spawned = ctx.clone();
+
+                let signal_r_callbacks = self.my_signal_r_callbacks.clone();
+
+                let connection_spawned = signalr_connection.clone();
+
+                let target = message.target.to_string();
+
+                let arguments = message.arguments.to_vec();
+
+                let invocation_id = message.invocation_id.map(|id| id.to_string());
+
+                let _handler = tokio::spawn(async move {
+                    #[cfg(feature = "my-telemetry")]
+                    let mut signal_r_telemetry = crate::SignalRTelemetry::new(ctx_spawned);
+                    let result = signal_r_callbacks
+                        .on(
+                            connection_spawned.clone(),
+                            message.headers,
+                            target,
+                            arguments,
                             #[cfg(feature = "my-telemetry")]
-                            signal_r_telemetry.tags
-                        })
+                            &mut signal_r_telemetry,
+                        )
                         .await;
 
-                        #[cfg(feature = "my-telemetry")]
-                        match _result {
+                    if let Some(invocation_id) = invocation_id {
+                        connection_spawned
+                            .send_completion(invocation_id.as_str(), &result)
+                            .await;
+                    }
+                    #[cfg(feature = "my-telemetry")]
+                    signal_r_telemetry.tags
+                });
+
+                #[cfg(feature = "my-telemetry")]
+                {
+                    let addr = *addr;
+                    let target = message.target.to_string();
+
+                    tokio::spawn(async move {
+                        match _handler.await {
                             Ok(tags) => {
                                 my_telemetry::TELEMETRY_INTERFACE
                                     .write_success(
                                         &ctx,
                                         started,
-                                        message.target.to_string(),
+                                        target,
                                         format!("Executed Ok",),
-                                        tags.add_ip(my_web_socket.addr.ip().to_string()).build(),
+                                        tags.add_ip(addr.ip().to_string()).build(),
                                     )
                                     .await;
                             }
@@ -160,63 +383,446 @@ impl<TCtx: Send + Sync + Default + 'static> my_http_server_web_sockets::MyWebSoc
                                     .write_fail(
                                         &ctx,
                                         started,
-                                        message.target.to_string(),
+                                        target,
                                         format!("{:?}", err),
                                         TelemetryEventTagsBuilder::new()
-                                            .add_ip(my_web_socket.addr.ip().to_string())
+                                            .add_ip(addr.ip().to_string())
                                             .build(),
                                     )
                                     .await;
                             }
                         }
+                    });
+                }
+            } else {
+                #[cfg(feature = "my-telemetry")]
+                let ctx = MyTelemetryContext::new();
+
+                let signal_r_callbacks = self.my_signal_r_callbacks.clone();
+                let connection_spawned = signalr_connection.clone();
+                let target = message.target.to_string();
+                let arguments = message.arguments.to_vec();
+                let invocation_id = message.invocation_id.map(|id| id.to_string());
+                let stream_ids: Vec<String> =
+                    message.stream_ids.iter().map(|id| id.to_string()).collect();
+
+                let mut streams = Vec::with_capacity(stream_ids.len());
+
+                for stream_id in &stream_ids {
+                    streams.push(
+                        signalr_connection
+                            .register_upload_stream(stream_id.as_str())
+                            .await,
+                    );
+                }
+
+                tokio::spawn(async move {
+                    #[cfg(feature = "my-telemetry")]
+                    let mut signal_r_telemetry = crate::SignalRTelemetry::new(ctx);
+                    let result = signal_r_callbacks
+                        .on_upload(
+                            connection_spawned.clone(),
+                            message.headers,
+                            target,
+                            arguments,
+                            streams,
+                            #[cfg(feature = "my-telemetry")]
+                            &mut signal_r_telemetry,
+                        )
+                        .await;
+
+                    for stream_id in &stream_ids {
+                        connection_spawned
+                            .complete_upload_stream(stream_id.as_str())
+                            .await;
                     }
 
-                    if packet_type == "6" {
-                        signalr_connection.send_ping_payload().await;
+                    if let Some(invocation_id) = invocation_id {
+                        connection_spawned
+                            .send_completion(invocation_id.as_str(), &result)
+                            .await;
                     }
-                } else {
-                    read_first_payload(signalr_connection, value).await
+                });
+            }
+        }
+
+        if packet_type == "2" {
+            if let Some(stream_item) = SignalrStreamItemMessage::parse(value) {
+                signalr_connection
+                    .push_upload_stream_item(stream_item.invocation_id, stream_item.item)
+                    .await;
+            }
+        }
+
+        if packet_type == "3" {
+            if let Some(completion) = SignalrCompletionMessage::parse(value) {
+                if !signalr_connection
+                    .complete_upload_stream(completion.invocation_id)
+                    .await
+                {
+                    signalr_connection.set_invocation_result(&completion).await;
+                }
+            }
+        }
+
+        if packet_type == "4" {
+            let message = SignalrMessage::parse(value);
+
+            if let Some(invocation_id) = message.invocation_id {
+                #[cfg(feature = "my-telemetry")]
+                let ctx = MyTelemetryContext::new();
+
+                let signal_r_callbacks = self.my_signal_r_callbacks.clone();
+                let connection_spawned = signalr_connection.clone();
+                let invocation_id = invocation_id.to_string();
+                let target = message.target.to_string();
+                let arguments = message.arguments.to_vec();
+
+                let cancel = signalr_connection
+                    .register_stream(invocation_id.as_str())
+                    .await;
+
+                tokio::spawn(async move {
+                    #[cfg(feature = "my-telemetry")]
+                    let mut signal_r_telemetry = crate::SignalRTelemetry::new(ctx);
+                    let stream = signal_r_callbacks
+                        .on_stream(
+                            connection_spawned.clone(),
+                            message.headers,
+                            target,
+                            arguments,
+                            #[cfg(feature = "my-telemetry")]
+                            &mut signal_r_telemetry,
+                        )
+                        .await;
+
+                    crate::process_stream_invocation(
+                        connection_spawned,
+                        invocation_id,
+                        stream,
+                        cancel,
+                    )
+                    .await;
+                });
+            }
+        }
+
+        if packet_type == "5" {
+            if let Some(cancel) = SignalrCancelInvocationMessage::parse(value) {
+                signalr_connection.cancel_stream(cancel.invocation_id).await;
+            }
+        }
+
+        if packet_type == "6" {
+            signalr_connection.send_ping_payload().await;
+        }
+
+        if packet_type == "8" {
+            if let Some(ack) = SignalrSequenceMessage::parse(value) {
+                signalr_connection.ack(ack.sequence_id).await;
+            }
+        }
+
+        if packet_type == "9" {
+            if let Some(sequence) = SignalrSequenceMessage::parse(value) {
+                if !signalr_connection
+                    .set_incoming_sequence(sequence.sequence_id)
+                    .await
+                {
+                    signalr_connection
+                        .close(
+                            Some("Sequence ID greater than amount of messages we've received."),
+                            false,
+                        )
+                        .await;
+                    crate::process_disconnect(
+                        &self.signalr_list,
+                        signalr_connection.get_list_index(),
+                        &self.my_signal_r_callbacks,
+                    )
+                    .await;
                 }
             }
         }
+
+        if packet_type == "7" {
+            crate::process_disconnect(
+                &self.signalr_list,
+                signalr_connection.get_list_index(),
+                &self.my_signal_r_callbacks,
+            )
+            .await;
+        }
+
+        Ok(())
     }
 }
 
-fn get_payload_type(payload: &str) -> &str {
+fn get_payload_type(payload: &str) -> Result<&str, String> {
     let json_reader = JsonFirstLineReader::new(payload.as_bytes());
     for line in json_reader {
-        let line = line.unwrap();
-        if line.get_name().unwrap() == "type" {
-            let result = line.get_value().unwrap();
-            return result.as_str().unwrap();
+        let line = line.map_err(|err| format!("Invalid message payload: {:?}", err))?;
+        let name = line
+            .get_name()
+            .map_err(|err| format!("Invalid message payload: {:?}", err))?;
+
+        if name == "type" {
+            let value = line
+                .get_value()
+                .map_err(|err| format!("Invalid message payload: {:?}", err))?;
+
+            return value
+                .as_str()
+                .ok_or_else(|| "Message type is not valid".to_string());
         }
     }
 
-    panic!("Packet type is not found")
+    Err("Message type is not found".to_string())
 }
 
-async fn read_first_payload<TCtx: Send + Sync + Default + 'static>(
+async fn read_first_payload<TCtx: Send + Sync + 'static>(
     signalr_connection: &Arc<MySignalrConnection<TCtx>>,
-    payload: &str,
-) {
-    let json_reader = JsonFirstLineReader::new(payload.as_bytes());
+    transfer_formats: &[SignalrTransferFormat],
+    payload: &[u8],
+) -> Option<usize> {
+    let handshake_end = payload.iter().position(|b| *b == 30);
+
+    let handshake = match handshake_end {
+        Some(handshake_end) => &payload[..handshake_end],
+        None => payload,
+    };
+
+    let hub_protocol = SignalrHandshakeRequest::parse(handshake)
+        .and_then(|request| request.get_hub_protocol())
+        .and_then(|hub_protocol| {
+            if hub_protocol == HubProtocol::MessagePack
+                && !transfer_formats.contains(&SignalrTransferFormat::Binary)
+            {
+                return Err(format!(
+                    "The protocol '{}' requires the Binary transfer format which is disabled.",
+                    hub_protocol.as_str()
+                ));
+            }
 
-    let mut protocol = false;
-    let mut version = false;
+            Ok(hub_protocol)
+        });
 
-    for line in json_reader {
-        let line = line.unwrap();
+    match hub_protocol {
+        Ok(hub_protocol) => {
+            signalr_connection.set_hub_protocol(hub_protocol).await;
+            signalr_connection.set_has_greeting();
+            signalr_connection
+                .send_raw_payload(crate::messages::generate_handshake_response(None))
+                .await;
+
+            if let Err(err) = signalr_connection.replay_unacknowledged().await {
+                signalr_connection.close(Some(err.as_str()), false).await;
+                return None;
+            }
+
+            match handshake_end {
+                Some(handshake_end) => Some(handshake_end + 1),
+                None => Some(payload.len()),
+            }
+        }
+        Err(err) => {
+            #[cfg(feature = "debug_ws")]
+            println!(
+                "Signalr {} handshake is rejected: {}",
+                signalr_connection.connection_id, err
+            );
+
+            signalr_connection
+                .send_raw_payload(crate::messages::generate_handshake_response(Some(
+                    err.as_str(),
+                )))
+                .await;
+            signalr_connection.disconnect().await;
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, net::SocketAddr, sync::Arc, time::Duration};
+
+    use futures::{channel::mpsc::UnboundedReceiver, stream::BoxStream, StreamExt};
+    use my_http_server::HttpFailResult;
+    use tokio::sync::Mutex;
+
+    use super::WebSocketCallbacks;
+    use crate::{
+        MySignalrCallbacks, MySignalrConnection, SignalRParam, SignalrConnectionSettings,
+        SignalrConnectionsList, SignalrContractDeserializer,
+    };
+
+    struct ClientName(String);
+
+    impl SignalrContractDeserializer for ClientName {
+        type Item = ClientName;
+
+        fn deserialize(data: &[&[u8]]) -> Result<Self::Item, String> {
+            Ok(ClientName(String::from_utf8(data[0].to_vec()).unwrap()))
+        }
+    }
+
+    struct InvokingCallbacks;
 
-        if line.get_name().unwrap() == "protocol" {
-            protocol = true;
+    #[async_trait::async_trait]
+    impl MySignalrCallbacks for InvokingCallbacks {
+        type TCtx = ();
+
+        async fn connected(
+            &self,
+            _connection: &Arc<MySignalrConnection<Self::TCtx>>,
+        ) -> Result<(), HttpFailResult> {
+            Ok(())
+        }
+
+        async fn disconnected(&self, _connection: &Arc<MySignalrConnection<Self::TCtx>>) {}
+
+        async fn on_ping(&self, _connection: &Arc<MySignalrConnection<Self::TCtx>>) {}
+
+        async fn on(
+            &self,
+            connection: Arc<MySignalrConnection<Self::TCtx>>,
+            _headers: Option<HashMap<String, String>>,
+            _action_name: String,
+            _data: Vec<u8>,
+            #[cfg(feature = "my-telemetry")] _ctx: &mut crate::SignalRTelemetry,
+        ) -> Result<Vec<Vec<u8>>, String> {
+            let name: ClientName = connection
+                .invoke("getName", &SignalRParam::None, Duration::from_secs(5))
+                .await
+                .map_err(|err| format!("{:?}", err))?;
+
+            Ok(vec![name.0.into_bytes()])
+        }
+
+        async fn on_stream(
+            &self,
+            _connection: Arc<MySignalrConnection<Self::TCtx>>,
+            _headers: Option<HashMap<String, String>>,
+            _action_name: String,
+            _data: Vec<u8>,
+            #[cfg(feature = "my-telemetry")] _ctx: &mut crate::SignalRTelemetry,
+        ) -> Result<BoxStream<'static, Vec<Vec<u8>>>, String> {
+            Err("Not supported".to_string())
+        }
+
+        async fn on_upload(
+            &self,
+            _connection: Arc<MySignalrConnection<Self::TCtx>>,
+            _headers: Option<HashMap<String, String>>,
+            _action_name: String,
+            _data: Vec<u8>,
+            _streams: Vec<UnboundedReceiver<Vec<u8>>>,
+            #[cfg(feature = "my-telemetry")] _ctx: &mut crate::SignalRTelemetry,
+        ) -> Result<Vec<Vec<u8>>, String> {
+            Err("Not supported".to_string())
         }
-        if line.get_name().unwrap() == "version" {
-            version = true;
+    }
+
+    fn create_web_socket_callbacks() -> WebSocketCallbacks<()> {
+        WebSocketCallbacks {
+            signalr_list: Arc::new(SignalrConnectionsList::new()),
+            my_signal_r_callbacks: Arc::new(InvokingCallbacks),
+            keep_alive_interval: Duration::from_secs(15),
+            transfer_formats: vec![],
+            allow_skip_negotiation: false,
+            outbound_queue: None,
+            pending_web_sockets: Mutex::new(HashMap::new()),
         }
     }
 
-    if protocol == true && version == true {
-        signalr_connection.set_has_greeting();
-        signalr_connection.send_raw_payload("{}".to_string()).await;
+    async fn read_event(receiver: &mut UnboundedReceiver<Vec<u8>>) -> String {
+        let event = tokio::time::timeout(Duration::from_secs(1), receiver.next())
+            .await
+            .unwrap()
+            .unwrap();
+
+        String::from_utf8(event).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_handler_can_invoke_client() {
+        let callbacks = create_web_socket_callbacks();
+        let addr: SocketAddr = "127.0.0.1:5000".parse().unwrap();
+
+        let connection = Arc::new(MySignalrConnection::new(
+            "connection".to_string(),
+            None,
+            None,
+            SignalrConnectionSettings {
+                negotiation_version: 0,
+                stateful_reconnect: None,
+                outbound_queue: None,
+            },
+            None,
+            (),
+        ));
+
+        let (sender, mut receiver) = futures::channel::mpsc::unbounded();
+        connection.attach_server_sent_events(sender).await;
+
+        tokio::time::timeout(
+            Duration::from_secs(1),
+            callbacks.handle_message(
+                &addr,
+                &connection,
+                "{\"type\":1,\"invocationId\":\"1\",\"target\":\"hello\",\"arguments\":[]}",
+            ),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+        let invocation = read_event(&mut receiver).await;
+        assert!(invocation.contains("\"invocationId\":\"0\""));
+        assert!(invocation.contains("\"target\":\"getName\""));
+
+        callbacks
+            .handle_message(
+                &addr,
+                &connection,
+                "{\"type\":3,\"invocationId\":\"0\",\"result\":\"client\"}",
+            )
+            .await
+            .unwrap();
+
+        let completion = read_event(&mut receiver).await;
+        assert!(completion.contains("\"type\":3"));
+        assert!(completion.contains("\"invocationId\":\"1\""));
+        assert!(completion.contains("\"result\":\"client\""));
+    }
+
+    #[tokio::test]
+    async fn test_malformed_message_is_rejected() {
+        let callbacks = create_web_socket_callbacks();
+        let addr: SocketAddr = "127.0.0.1:5000".parse().unwrap();
+
+        let connection = Arc::new(MySignalrConnection::new(
+            "connection".to_string(),
+            None,
+            None,
+            SignalrConnectionSettings {
+                negotiation_version: 0,
+                stateful_reconnect: None,
+                outbound_queue: None,
+            },
+            None,
+            (),
+        ));
+
+        assert!(callbacks
+            .handle_message(&addr, &connection, "{\"type\":")
+            .await
+            .is_err());
+
+        assert!(callbacks
+            .handle_message(&addr, &connection, "{\"target\":\"hello\"}")
+            .await
+            .is_err());
     }
 }